@@ -0,0 +1,64 @@
+// Times rendering a screenshot over a background decoded from the same format (RGBA8) and
+// from another format (RGB8), which has to be converted
+// Run with "cargo bench --bench background"
+
+use std::path::PathBuf;
+use std::process::Command;
+use std::time::{Duration, Instant};
+
+use image::{RgbImage, RgbaImage};
+
+const SIZE: u32 = 2000;
+const RUNS: usize = 10;
+
+const LOG: &str = "2022-01-01 00:00:00,000\talice\t0\t0\t5\tuser place\n";
+
+fn tmp_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(name)
+}
+
+// Best of several runs, the least disturbed by other processes
+fn time_render(bg: &PathBuf, src: &PathBuf, dst: &PathBuf) -> Duration {
+    (0..RUNS)
+        .map(|_| {
+            let start = Instant::now();
+            let output = Command::new(env!("CARGO_BIN_EXE_pxlslog-explorer"))
+                .args(["render", "--screenshot", "-s"])
+                .arg(src)
+                .arg("-d")
+                .arg(dst)
+                .arg("--bg")
+                .arg(bg)
+                .output()
+                .unwrap();
+            assert!(
+                output.status.success(),
+                "{}",
+                String::from_utf8_lossy(&output.stderr)
+            );
+            start.elapsed()
+        })
+        .min()
+        .unwrap()
+}
+
+fn main() {
+    let src = tmp_path("bench_background.log");
+    std::fs::write(&src, LOG).unwrap();
+    let dst = tmp_path("bench_background_out.png");
+
+    let rgba = tmp_path("bench_background_rgba.png");
+    RgbaImage::from_fn(SIZE, SIZE, |x, y| image::Rgba([x as u8, y as u8, 0, 255]))
+        .save(&rgba)
+        .unwrap();
+    let rgb = tmp_path("bench_background_rgb.png");
+    RgbImage::from_fn(SIZE, SIZE, |x, y| image::Rgb([x as u8, y as u8, 0]))
+        .save(&rgb)
+        .unwrap();
+
+    for (name, bg) in [("rgba8 (same format)", &rgba), ("rgb8 (cross format)", &rgb)] {
+        let best = time_render(bg, &src, &dst);
+        let secs = best.as_secs_f64();
+        println!("{:<20} {}x{} best of {}: {:.3}s", name, SIZE, SIZE, RUNS, secs);
+    }
+}
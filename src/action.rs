@@ -1,10 +1,14 @@
+use std::fmt;
+use std::num::ParseIntError;
+use std::str::FromStr;
+
 use chrono::NaiveDateTime;
 use clap::ArgEnum;
 
-use crate::error::{RuntimeError, RuntimeErrorKind};
+use crate::error::{RuntimeError, RuntimeErrorKind, RuntimeResult};
 
 // TODO: Move ArgEnum into filter.rs?
-#[derive(Debug, PartialEq, Copy, Clone, ArgEnum)]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone, ArgEnum)]
 pub enum ActionKind {
     Place,
     Undo,
@@ -32,6 +36,12 @@ impl<'a> TryFrom<&'a str> for ActionKind {
 
 impl ToString for ActionKind {
     fn to_string(&self) -> String {
+        self.as_str().to_string()
+    }
+}
+
+impl ActionKind {
+    pub fn as_str(&self) -> &'static str {
         match self {
             ActionKind::Place => "user place",
             ActionKind::Undo => "user undo",
@@ -40,7 +50,6 @@ impl ToString for ActionKind {
             ActionKind::RollbackUndo => "rollback undo",
             ActionKind::Nuke => "console nuke",
         }
-        .to_string()
     }
 }
 
@@ -50,9 +59,14 @@ pub enum IdentifierRef<'a> {
     Username(&'a str),
 }
 
+// Hashes are hex encoded sha256 digests, anything else is a username
+pub fn is_hash(s: &str) -> bool {
+    s.len() == 64 && s.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
 impl<'a> From<&'a str> for IdentifierRef<'a> {
     fn from(s: &'a str) -> Self {
-        if s.len() == 64 {
+        if is_hash(s) {
             IdentifierRef::Hash(s)
         } else {
             IdentifierRef::Username(s)
@@ -100,7 +114,7 @@ pub enum Identifier {
 
 impl From<&str> for Identifier {
     fn from(s: &str) -> Self {
-        if s.len() == 512 {
+        if is_hash(s) {
             Identifier::Hash(s.to_owned())
         } else {
             Identifier::Username(s.to_owned())
@@ -147,66 +161,190 @@ impl Identifier {
     }
 }
 
+// Palette index of a placement, pxls logs transparent placements as "-1"
+#[derive(Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Copy, Clone)]
+pub enum Index {
+    Color(usize),
+    Transparent,
+}
+
+impl FromStr for Index {
+    type Err = ParseIntError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "-1" => Ok(Index::Transparent),
+            _ => Ok(Index::Color(s.parse()?)),
+        }
+    }
+}
+
+impl fmt::Display for Index {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Index::Color(i) => write!(f, "{}", i),
+            Index::Transparent => write!(f, "-1"),
+        }
+    }
+}
+
+impl Index {
+    // Palette index, None if transparent
+    pub fn color(&self) -> Option<usize> {
+        match self {
+            Index::Color(i) => Some(*i),
+            Index::Transparent => None,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct ActionRef<'a> {
     pub time: NaiveDateTime,
     pub user: IdentifierRef<'a>,
     pub x: u32,
     pub y: u32,
-    pub index: usize,
+    pub index: Index,
     pub kind: ActionKind,
 }
 
+// Fields of a log line, see "--input-columns"
+#[derive(Debug, PartialEq, Eq, Copy, Clone, ArgEnum)]
+pub enum Column {
+    Time,
+    User,
+    X,
+    Y,
+    Index,
+    Kind,
+}
+
+// Standard pxls log order
+pub const DEFAULT_COLUMNS: [Column; 6] = [
+    Column::Time,
+    Column::User,
+    Column::X,
+    Column::Y,
+    Column::Index,
+    Column::Kind,
+];
+
 // Todo: Remove
 impl<'a> TryFrom<&'a str> for ActionRef<'a> {
     type Error = RuntimeError;
 
     fn try_from(s: &'a str) -> Result<Self, Self::Error> {
+        Self::parse(s, &DEFAULT_COLUMNS)
+    }
+}
+
+impl<'a> ToString for ActionRef<'a> {
+    fn to_string(&self) -> String {
+        let mut out = String::new();
+        // Writing to a String cannot fail
+        self.write_to(&mut out).unwrap();
+        out
+    }
+}
+
+impl<'a> ActionRef<'a> {
+    // Parses tab delimited fields in the given column order, columns must contain every field once
+    pub fn parse(s: &'a str, columns: &[Column]) -> RuntimeResult<Self> {
+        let mut fields = [""; 6];
         let mut iter = s.split_terminator(|c| c == '\t');
+        for column in columns {
+            fields[*column as usize] = iter
+                .next()
+                .ok_or(RuntimeError::new(RuntimeErrorKind::UnexpectedEof))?;
+        }
 
         Ok(ActionRef {
             time: NaiveDateTime::parse_from_str(
-                iter.next()
-                    .ok_or(RuntimeError::new(RuntimeErrorKind::UnexpectedEof))?,
+                fields[Column::Time as usize],
                 "%Y-%m-%d %H:%M:%S,%3f",
             )?,
-            user: IdentifierRef::from(
-                iter.next()
-                    .ok_or(RuntimeError::new(RuntimeErrorKind::UnexpectedEof))?,
-            ),
-            x: iter
-                .next()
-                .ok_or(RuntimeError::new(RuntimeErrorKind::UnexpectedEof))?
-                .parse()?,
-            y: iter
-                .next()
-                .ok_or(RuntimeError::new(RuntimeErrorKind::UnexpectedEof))?
-                .parse()?,
-            index: iter
-                .next()
-                .ok_or(RuntimeError::new(RuntimeErrorKind::UnexpectedEof))?
-                .parse()?,
-            kind: ActionKind::try_from(
-                iter.next()
-                    .ok_or(RuntimeError::new(RuntimeErrorKind::UnexpectedEof))?,
-            )?,
+            user: IdentifierRef::from(fields[Column::User as usize]),
+            x: fields[Column::X as usize].parse()?,
+            y: fields[Column::Y as usize].parse()?,
+            index: fields[Column::Index as usize].parse()?,
+            kind: ActionKind::try_from(fields[Column::Kind as usize])?,
         })
     }
+
+    // Writes the tab delimited fields without allocating intermediate strings
+    pub fn write_to(&self, w: &mut impl fmt::Write) -> fmt::Result {
+        write!(
+            w,
+            "{}\t{}\t{}\t{}\t{}\t{}",
+            self.time.format("%Y-%m-%d %H:%M:%S,%3f"),
+            self.user.get(),
+            self.x,
+            self.y,
+            self.index,
+            self.kind.as_str(),
+        )
+    }
+
+    // Appends the action and a newline to a reusable buffer
+    pub fn to_string_into(&self, buf: &mut String) {
+        // Writing to a String cannot fail
+        self.write_to(buf).unwrap();
+        buf.push('\n');
+    }
 }
 
-impl<'a> ToString for ActionRef<'a> {
-    fn to_string(&self) -> String {
-        let mut out = self.time.format("%Y-%m-%d %H:%M:%S,%3f").to_string();
-        out += "\t";
-        out += &self.user.to_string();
-        out += "\t";
-        out += &self.x.to_string();
-        out += "\t";
-        out += &self.y.to_string();
-        out += "\t";
-        out += &self.index.to_string();
-        out += "\t";
-        out += &self.kind.to_string();
-        out
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::Rng;
+
+    const KINDS: [&str; 6] = [
+        "user place",
+        "user undo",
+        "mod overwrite",
+        "rollback",
+        "rollback undo",
+        "console nuke",
+    ];
+    const CHARS: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789_-";
+
+    // Complete actions are written back exactly as parsed
+    #[test]
+    fn round_trip() {
+        // Fixed seed for reproducible failures
+        let mut rng = Rng::new(0x5EED);
+        let mut buf = String::new();
+        for _ in 0..500 {
+            // A quarter of the users are hashes
+            let user: String = match rng.below(4) {
+                0 => (0..64)
+                    .map(|_| b"0123456789abcdef"[rng.below(16)] as char)
+                    .collect(),
+                _ => (0..1 + rng.below(32))
+                    .map(|_| CHARS[rng.below(CHARS.len())] as char)
+                    .collect(),
+            };
+            let line = format!(
+                "{:04}-{:02}-{:02} {:02}:{:02}:{:02},{:03}\t{}\t{}\t{}\t{}\t{}\n",
+                2017 + rng.below(10),
+                1 + rng.below(12),
+                1 + rng.below(28),
+                rng.below(24),
+                rng.below(60),
+                rng.below(60),
+                rng.below(1000),
+                user,
+                rng.below(5000),
+                rng.below(5000),
+                rng.below(256),
+                KINDS[rng.below(6)],
+            );
+
+            buf.clear();
+            ActionRef::try_from(line.trim_end())
+                .unwrap()
+                .to_string_into(&mut buf);
+            assert_eq!(buf, line);
+        }
     }
 }
@@ -1,18 +1,16 @@
-use std::fs::{self, File, OpenOptions};
+use std::fs::{self, OpenOptions};
 use std::io::prelude::*;
 use std::path::Path;
-use std::sync::atomic::{AtomicI32, Ordering};
 
-use crate::action::{ActionKind, ActionRef};
+use crate::action::{self, ActionKind, ActionRef, IdentifierRef, Index};
 use crate::commands::{CommandInput, Command};
 use crate::error::{ConfigError, ConfigResult, RuntimeError, RuntimeErrorKind, RuntimeResult};
-use crate::util::Region;
-use crate::Cli;
+use crate::util::{self, Glob, Region};
+use crate::{Cli, OnReset};
 
 use chrono::NaiveDateTime;
-use clap::{ArgGroup, Args};
-use rayon::iter::ParallelIterator;
-use rayon::str::ParallelString;
+use clap::{ArgGroup, Args, CommandFactory};
+use rayon::prelude::*;
 use sha2::{Digest, Sha256};
 
 // TODO: Custom handling of specific types (e.g. region)
@@ -23,7 +21,8 @@ use sha2::{Digest, Sha256};
 pub struct FilterInput {
     #[clap(short, long)]
     #[clap(value_name("PATH"))]
-    #[clap(help = "Filepath of input log file", display_order = 0)]
+    #[clap(help = "Filepath or url of input log file", display_order = 0)]
+    #[clap(long_help = "Filepath or url of input log file, urls are downloaded in full before filtering (Requires the \"url\" feature)")]
     src: Option<String>,
     #[clap(short, long)]
     #[clap(value_name("PATH"))]
@@ -38,13 +37,15 @@ pub struct FilterInput {
         display_order = 2
     )]
     modify: bool,
-    #[clap(long, parse(try_from_str))]
+    #[clap(long, parse(try_from_str = util::parse_timestamp))]
     #[clap(value_name("TIMESTAMP"))]
-    #[clap(help = "Only include entries after this date [%Y-%m-%dT%H:%M:%S%.f]")]
+    #[clap(help = "Only include entries after this date")]
+    #[clap(long_help = "Only include entries after this date [e.g. \"2022-05-01T10:00:00\", \"2022-05-01 10:00\" or \"2022-05-01\"]")]
     after: Option<NaiveDateTime>,
-    #[clap(long, parse(try_from_str))]
+    #[clap(long, parse(try_from_str = util::parse_timestamp))]
     #[clap(value_name("TIMESTAMP"))]
-    #[clap(help = "Only include entries before this date [%Y-%m-%dT%H:%M:%S%.f]")]
+    #[clap(help = "Only include entries before this date")]
+    #[clap(long_help = "Only include entries before this date [e.g. \"2022-05-01T10:00:00\", \"2022-05-01 10:00\" or \"2022-05-01\"]")]
     before: Option<NaiveDateTime>,
     #[clap(long)]
     #[clap(multiple_values(true))]
@@ -52,16 +53,24 @@ pub struct FilterInput {
     #[clap(help = "Only include entries with provided colors")]
     color: Vec<usize>,
     #[clap(long, parse(try_from_str))]
-    #[clap(max_values(4))]
+    #[clap(number_of_values(4))]
+    #[clap(multiple_occurrences(true))]
     #[clap(value_name("INT"))]
     #[clap(help = "Only include entries within a region [\"x1 y1 x2 y2\"]")]
+    #[clap(long_help = "Only include entries within a region [\"x1 y1 x2 y2\"]
+Repeat to include entries within any of the regions")]
     region: Vec<u32>,
     #[clap(long)]
     #[clap(multiple_values(true))]
     #[clap(value_name("STRING"))]
     #[clap(help = "Only include entries that belong to this username")]
+    #[clap(long_help = "Only include entries that belong to this username, supports globs (\"*\" matches any sequence, \"?\" any character)")]
     username: Vec<String>,
     #[clap(long)]
+    #[clap(requires("username"))]
+    #[clap(help = "Ignore case when matching usernames")]
+    user_ignore_case: bool,
+    #[clap(long)]
     #[clap(multiple_values(true))]
     #[clap(value_name("STRING"))]
     #[clap(help = "Only include entries that belong to this hash")]
@@ -75,22 +84,31 @@ pub struct FilterInput {
     #[clap(value_name("ENUM"))]
     #[clap(help = "Only include entries with this action", display_order = 9999)]
     action: Vec<ActionKind>,
+    #[clap(long)]
+    #[clap(multiple_values(true))]
+    #[clap(value_name("PATH"))]
+    #[clap(conflicts_with_all(&["src", "modify"]))]
+    #[clap(help = "Merge multiple logs into one ordered by time")]
+    #[clap(long_help = "Merge multiple logs (filepaths or urls) into one ordered by time, entries that appear in several logs are only written once
+Other filters are applied to the merged log")]
+    merge: Vec<String>,
 }
 
 pub struct FilterData {
     src: Option<String>,
     dst: Option<String>,
     users: Identifier,
-    region: Option<Region<u32>>,
+    regions: Vec<Region<u32>>,
     after: Option<NaiveDateTime>,
     before: Option<NaiveDateTime>,
     color: Vec<usize>,
     kind: Vec<ActionKind>,
+    merge: Vec<String>,
 }
 
 enum Identifier {
     Hash(Vec<String>),
-    Username(Vec<String>),
+    Username(Vec<Glob>),
     None,
 }
 
@@ -103,7 +121,12 @@ impl CommandInput<FilterData> for FilterInput {
         };
 
         let users = if self.username.len() > 0 {
-            Identifier::Username(self.username.clone())
+            Identifier::Username(
+                self.username
+                    .iter()
+                    .map(|u| Glob::new(u, self.user_ignore_case))
+                    .collect(),
+            )
         } else if let Some(hash) = &self.hash {
             Identifier::Hash(hash.to_owned())
         } else if let Some(src) = &self.hash_src {
@@ -115,15 +138,31 @@ impl CommandInput<FilterData> for FilterInput {
             Identifier::None
         };
 
+        // Clap only checks the total number of values, so count them per occurrence
+        if !self.region.is_empty() {
+            let matches = Cli::command().get_matches();
+            let filter = matches.subcommand_matches("filter");
+            let occurrences = filter.map_or(0, |m| m.occurrences_of("region") as usize);
+            if self.region.len() != 4 * occurrences {
+                Err(ConfigError::new("region", "each region requires 4 values"))?
+            }
+        }
+        let regions = self
+            .region
+            .chunks(4)
+            .filter_map(Region::from_slice)
+            .collect();
+
         Ok(FilterData {
             src: self.src.clone(),
             dst,
             users,
-            region: Region::from_slice(&self.region),
+            regions,
             after: self.after,
             before: self.before,
             color: self.color.clone(),
             kind: self.action.clone(),
+            merge: self.merge.clone(),
         })
     }
 }
@@ -148,62 +187,100 @@ impl FilterInput {
     }
 
     fn verify_hash(hash: &str) -> Option<&str> {
-        if hash.len() == 512 {
-            None
-        } else {
+        if action::is_hash(hash) {
             Some(hash)
+        } else {
+            None
         }
     }
 }
 
 impl Command for FilterData {
     fn run(&self, settings: &Cli) -> RuntimeResult<()> {
-        // TODO: No atomics?
-        let passed = AtomicI32::new(0);
-        let total = AtomicI32::new(0);
-
-        let mut data = String::new();
-        match &self.src {
-            Some(path) => File::open(path)?.read_to_string(&mut data)?,
-            None => std::io::stdin().lock().read_to_string(&mut data)?,
+        let logs = match (&self.src, self.merge.is_empty()) {
+            (_, false) => self.merge.clone(),
+            (Some(path), true) => vec![path.to_owned()],
+            (None, true) => vec![],
         };
 
-        let filename = match &self.src {
-            Some(path) => Path::new(path)
-                .file_name()
-                .unwrap()
-                .to_string_lossy()
-                .into_owned(),
-            None => "STDIN".to_string(),
-        };
+        let mut data = Vec::with_capacity(logs.len());
+        for path in &logs {
+            data.push(util::read_log(path).map_err(|e| RuntimeError::from_err(e, path, 0))?);
+        }
+        if logs.is_empty() {
+            let mut stdin = String::new();
+            std::io::stdin().lock().read_to_string(&mut stdin)?;
+            data.push(stdin);
+        }
 
-        let out: String = data
-            .as_parallel_string()
-            .par_lines()
-            .inspect(|_| {
-                total.fetch_add(1, Ordering::SeqCst);
-            })
-            .filter_map(|s| match ActionRef::try_from(s) {
-                Ok(a) => {
-                    if self.is_filtered(&a) {
-                        Some(a.to_string() + "\n")
-                    } else {
-                        None
-                    }
+        // Canvas n of every log is merged and filtered together when splitting
+        let mut canvases: Vec<Vec<ActionRef>> = vec![vec![]];
+        for (i, data) in data.iter().enumerate() {
+            let filename = match logs.get(i) {
+                Some(path) => Path::new(path)
+                    .file_name()
+                    .unwrap()
+                    .to_string_lossy()
+                    .into_owned(),
+                None => "STDIN".to_string(),
+            };
+            let actions = super::parse_actions(data, &filename, settings, None)?;
+            let segments = match settings.on_reset {
+                Some(OnReset::Split) => super::split_resets(&actions),
+                _ => vec![&actions[..]],
+            };
+            for (n, segment) in segments.into_iter().enumerate() {
+                if n == canvases.len() {
+                    canvases.push(vec![]);
                 }
-                Err(e) => {
-                    if settings.verbose {
-                        eprintln!("{}", RuntimeError::from_err(e, &filename, 0));
-                    }
-                    None
-                } // TODO
-            })
-            .inspect(|_| {
-                passed.fetch_add(1, Ordering::SeqCst);
+                canvases[n].extend_from_slice(segment);
+            }
+        }
+
+        let single = canvases.len() == 1;
+        for (n, actions) in canvases.into_iter().enumerate() {
+            let dst = match single {
+                true => self.dst.clone(),
+                false => self.dst.as_deref().map(|path| super::canvas_path(path, n)),
+            };
+            self.filter_canvas(actions, dst, logs.len(), settings)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl FilterData {
+    fn filter_canvas(
+        &self,
+        mut actions: Vec<ActionRef>,
+        dst: Option<String>,
+        logs: usize,
+        settings: &Cli,
+    ) -> RuntimeResult<()> {
+        if !self.merge.is_empty() {
+            let total = actions.len();
+            actions = Self::merge(actions);
+            if settings.verbose {
+                println!(
+                    "Merged {} entries from {} logs ({} duplicates removed)",
+                    total,
+                    logs,
+                    total - actions.len()
+                );
+            }
+        }
+
+        let passed: Vec<&ActionRef> = actions.par_iter().filter(|a| self.is_filtered(a)).collect();
+        let out: String = passed
+            .par_iter()
+            .fold(String::new, |mut buf, a| {
+                a.to_string_into(&mut buf);
+                buf
             })
             .collect();
 
-        match &self.dst {
+        match &dst {
             Some(path) => {
                 OpenOptions::new()
                     .create_new(settings.noclobber)
@@ -219,74 +296,86 @@ impl Command for FilterData {
         };
 
         if settings.verbose {
-            println!(
-                "Returned {} of {} entries",
-                passed.load(Ordering::Acquire),
-                total.load(Ordering::Acquire)
-            );
+            println!("Returned {} of {} entries", passed.len(), actions.len());
         }
 
         Ok(())
     }
-}
 
-impl FilterData {
     // TODO: Improve how tokens are inputted
-    // TODO: Split into individual functions
+    // Cheapest predicates are checked first, returning as soon as one fails
     fn is_filtered(&self, action: &ActionRef) -> bool {
-        let mut out = true;
-
-        if let Some(time) = self.after {
-            out &= time <= action.time;
+        if !self.regions.is_empty() && !self.in_regions(action.x, action.y) {
+            return false;
         }
-        if let Some(time) = self.before {
-            out &= time >= action.time;
+        if !self.color.is_empty() && !matches!(action.index, Index::Color(i) if self.color.contains(&i)) {
+            return false;
         }
-        if let Some(region) = self.region {
-            out &= region.contains(action.x, action.y);
+        if !self.kind.is_empty() && !self.kind.contains(&action.kind) {
+            return false;
         }
-        if self.color.len() > 0 {
-            let mut temp = false;
-            for color in &self.color {
-                temp |= *color == action.index;
-            }
-            out &= temp;
+        if self.after.is_some_and(|time| time > action.time) {
+            return false;
+        }
+        if self.before.is_some_and(|time| time < action.time) {
+            return false;
         }
-        if self.kind.len() > 0 {
-            let mut temp = false;
-            for kind in &self.kind {
-                temp |= *kind == action.kind;
+
+        // Hashing is expen$ive, so users are checked last
+        match &self.users {
+            Identifier::Hash(hashes) => {
+                let time = action.time.format("%Y-%m-%d %H:%M:%S,%3f").to_string();
+                hashes.iter().any(|hash| {
+                    let mut hasher = Sha256::new();
+                    hasher.update(time.as_bytes());
+                    hasher.update(",");
+                    hasher.update(action.x.to_string().as_bytes());
+                    hasher.update(",");
+                    hasher.update(action.y.to_string().as_bytes());
+                    hasher.update(",");
+                    hasher.update(action.index.to_string().as_bytes());
+                    hasher.update(",");
+                    hasher.update(hash.as_bytes());
+                    let digest = hex::encode(hasher.finalize());
+                    &digest[..] == hash
+                })
             }
-            out &= temp;
+            Identifier::Username(usernames) => match action.user {
+                IdentifierRef::Username(user) => usernames.iter().any(|u| u.matches(user)),
+                IdentifierRef::Hash(_) => false,
+            },
+            Identifier::None => true,
         }
-        // Skip if line didn't pass (Hashing is expen$ive)
-        if out == true {
-            match &self.users {
-                Identifier::Hash(hashes) => {
-                    let mut temp = false;
-                    let time = action.time.format("%Y-%m-%d %H:%M:%S,%3f").to_string();
-                    for hash in hashes {
-                        let mut hasher = Sha256::new();
-                        hasher.update(time.as_bytes());
-                        hasher.update(",");
-                        hasher.update(action.x.to_string().as_bytes());
-                        hasher.update(",");
-                        hasher.update(action.y.to_string().as_bytes());
-                        hasher.update(",");
-                        hasher.update(action.index.to_string().as_bytes());
-                        hasher.update(",");
-                        hasher.update(hash.as_bytes());
-                        let digest = hex::encode(hasher.finalize());
-                        temp |= &digest[..] == hash;
-                    }
-                    out &= temp;
-                }
-                Identifier::Username(_) => {
-                    todo!()
-                }
-                Identifier::None => (),
+    }
+
+    // Stable sort keeps log order for equal timestamps, duplicates can only share a timestamp
+    fn merge(mut actions: Vec<ActionRef>) -> Vec<ActionRef> {
+        actions.sort_by_key(|a| a.time);
+
+        let mut merged: Vec<ActionRef> = Vec::with_capacity(actions.len());
+        for action in actions {
+            let duplicate = merged
+                .iter()
+                .rev()
+                .take_while(|a| a.time == action.time)
+                .any(|a| {
+                    a.user.get() == action.user.get()
+                        && (a.x, a.y, a.index, a.kind)
+                            == (action.x, action.y, action.index, action.kind)
+                });
+            if !duplicate {
+                merged.push(action);
             }
         }
-        out
+        merged
+    }
+
+    // Axis bounds are compared inline as this runs once per line
+    fn in_regions(&self, x: u32, y: u32) -> bool {
+        self.regions.iter().any(|region| {
+            let (x1, y1) = region.start();
+            let (x2, y2) = region.end();
+            x >= x1 && x < x2 && y >= y1 && y < y2
+        })
     }
 }
@@ -3,10 +3,17 @@ pub mod render;
 pub mod stats;
 
 use crate::{
-    error::{ConfigResult, RuntimeResult},
-    Cli,
+    action::{ActionRef, IdentifierRef},
+    error::{ConfigResult, RuntimeError, RuntimeErrorKind, RuntimeResult},
+    Cli, OnReset,
 };
 
+use std::io::BufRead;
+use std::time::Instant;
+
+use chrono::{Duration, NaiveDateTime};
+use rayon::prelude::*;
+
 pub trait CommandInput<T>
 where
     T: Command,
@@ -16,4 +23,234 @@ where
 
 pub trait Command {
     fn run(&self, settings: &Cli) -> RuntimeResult<()>;
-}
\ No newline at end of file
+}
+// Number of skipped lines shown when not verbose
+const ERROR_SAMPLES: usize = 3;
+
+// Backwards jumps in time larger than this are a canvas reset rather than out of order writes
+const RESET_THRESHOLD: Duration = Duration::hours(1);
+
+// Malformed lines are skipped unless running in strict mode or exceeding the error limit
+// Parsing stops early once limit actions are read
+pub fn parse_actions<'a>(
+    data: &'a str,
+    src: &str,
+    settings: &Cli,
+    limit: Option<usize>,
+) -> RuntimeResult<Vec<ActionRef<'a>>> {
+    let start = Instant::now();
+    let parse = |(i, s): (usize, &'a str)| {
+        ActionRef::parse(s, &settings.input_columns)
+            .map_err(|e| RuntimeError::from_err(e, src, i + 1))
+    };
+    let results: Box<dyn Iterator<Item = RuntimeResult<ActionRef>>> = match limit {
+        // Lazily parse so huge logs are not read in full
+        Some(_) => Box::new(data.lines().enumerate().map(parse)),
+        None => {
+            let lines: Vec<(usize, &str)> = data.lines().enumerate().collect();
+            let results: Vec<RuntimeResult<ActionRef>> = lines.into_par_iter().map(parse).collect();
+            Box::new(results.into_iter())
+        }
+    };
+
+    let mut actions = Vec::new();
+    let mut errors = SkippedLines::default();
+    let mut users = UserKinds::default();
+    let mut resets = Resets::default();
+    for (i, result) in results.enumerate() {
+        match result {
+            Ok(action) => {
+                users.add(&action.user);
+                resets.check(&action, src, i + 1, settings)?;
+                actions.push(action);
+                if Some(actions.len()) == limit {
+                    break;
+                }
+            }
+            Err(e) => errors.skip(e, settings)?,
+        }
+    }
+    errors.finish(src, settings)?;
+    users.finish(src);
+    resets.finish(src, settings);
+    if settings.verbose {
+        print_throughput("Parsed", actions.len(), "actions", start);
+    }
+
+    Ok(actions)
+}
+
+// Visits each action while reading line by line, without collecting the log into memory
+// Malformed lines are handled as in parse_actions
+pub fn for_each_action<R, F>(mut input: R, src: &str, settings: &Cli, mut f: F) -> RuntimeResult<()>
+where
+    R: BufRead,
+    F: FnMut(&ActionRef),
+{
+    let start = Instant::now();
+    let mut count = 0;
+    let mut errors = SkippedLines::default();
+    let mut users = UserKinds::default();
+    let mut resets = Resets::default();
+    let mut line = String::new();
+    for i in 1.. {
+        line.clear();
+        if input
+            .read_line(&mut line)
+            .map_err(|e| RuntimeError::from_err(e, src, i))?
+            == 0
+        {
+            break;
+        }
+
+        let line = line.trim_end_matches(&['\r', '\n'][..]);
+        match ActionRef::parse(line, &settings.input_columns) {
+            Ok(action) => {
+                users.add(&action.user);
+                resets.check(&action, src, i, settings)?;
+                f(&action);
+                count += 1;
+            }
+            Err(e) => errors.skip(RuntimeError::from_err(e, src, i), settings)?,
+        }
+    }
+    errors.finish(src, settings)?;
+    users.finish(src);
+    resets.finish(src, settings);
+    if settings.verbose {
+        print_throughput("Parsed", count, "actions", start);
+    }
+    Ok(())
+}
+
+// Splits actions in log order at each canvas reset, see "--on-reset"
+pub fn split_resets<'a, 'b>(actions: &'b [ActionRef<'a>]) -> Vec<&'b [ActionRef<'a>]> {
+    let mut segments = vec![];
+    let mut start = 0;
+    let mut latest = None;
+    for (i, action) in actions.iter().enumerate() {
+        if is_reset(latest, action.time) {
+            segments.push(&actions[start..i]);
+            start = i;
+            latest = None;
+        }
+        latest = latest.max(Some(action.time));
+    }
+    segments.push(&actions[start..]);
+    segments
+}
+
+fn is_reset(latest: Option<NaiveDateTime>, time: NaiveDateTime) -> bool {
+    latest.is_some_and(|latest| latest - time > RESET_THRESHOLD)
+}
+
+// Output path of a split canvas, suffixed before the extension (e.g. "out_canvas1.png")
+pub fn canvas_path(path: &str, n: usize) -> String {
+    match path.rsplit_once('.') {
+        Some((stem, ext)) => format!("{}_canvas{}.{}", stem, n, ext),
+        None => format!("{}_canvas{}", path, n),
+    }
+}
+
+// e.g. "Parsed 5200000 actions in 3.10s (1677419/s)"
+pub fn print_throughput(verb: &str, count: usize, unit: &str, start: Instant) {
+    let secs = start.elapsed().as_secs_f64();
+    let rate = match secs > 0.0 {
+        true => count as f64 / secs,
+        false => 0.0,
+    };
+    eprintln!("{} {} {} in {:.2}s ({:.0}/s)", verb, count, unit, secs, rate);
+}
+
+#[derive(Default)]
+struct SkippedLines {
+    count: usize,
+}
+
+impl SkippedLines {
+    fn skip(&mut self, e: RuntimeError, settings: &Cli) -> RuntimeResult<()> {
+        if settings.strict {
+            return Err(e);
+        }
+        if settings.verbose || self.count < ERROR_SAMPLES {
+            eprintln!("{}", e);
+        }
+        self.count += 1;
+        Ok(())
+    }
+
+    fn finish(self, src: &str, settings: &Cli) -> RuntimeResult<()> {
+        if self.count > 0 {
+            eprintln!("Skipped {} malformed lines in {}", self.count, src);
+        }
+        if let Some(max) = settings.max_errors {
+            if self.count > max {
+                Err(RuntimeError::new_with_file(
+                    RuntimeErrorKind::ErrorLimit(max),
+                    src,
+                    0,
+                ))?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+// Tracks the latest time seen, a canvas starts over whenever time jumps far backwards
+#[derive(Default)]
+struct Resets {
+    latest: Option<NaiveDateTime>,
+    count: usize,
+}
+
+impl Resets {
+    fn check(
+        &mut self,
+        action: &ActionRef,
+        src: &str,
+        line: usize,
+        settings: &Cli,
+    ) -> RuntimeResult<()> {
+        if is_reset(self.latest, action.time) {
+            if let OnReset::Error = settings.on_reset.unwrap_or_default() {
+                Err(RuntimeError::new_with_file(RuntimeErrorKind::OutOfOrder, src, line))?
+            }
+            self.latest = None;
+            self.count += 1;
+        }
+        self.latest = self.latest.max(Some(action.time));
+        Ok(())
+    }
+
+    fn finish(self, src: &str, settings: &Cli) {
+        if self.count > 0 && settings.verbose {
+            eprintln!("Found {} canvas resets in {}", self.count, src);
+        }
+    }
+}
+
+// Users are told apart by length, a log should only contain one kind (e.g. unless concatenated)
+#[derive(Default)]
+struct UserKinds {
+    hashes: usize,
+    usernames: usize,
+}
+
+impl UserKinds {
+    fn add(&mut self, user: &IdentifierRef) {
+        match user {
+            IdentifierRef::Hash(_) => self.hashes += 1,
+            IdentifierRef::Username(_) => self.usernames += 1,
+        }
+    }
+
+    fn finish(self, src: &str) {
+        if self.hashes > 0 && self.usernames > 0 {
+            eprintln!(
+                "Warning: {} mixes usernames ({} actions) and hashes ({} actions), user filters only match one kind",
+                src, self.usernames, self.hashes
+            );
+        }
+    }
+}
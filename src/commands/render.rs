@@ -1,19 +1,21 @@
+use std::collections::HashMap;
 use std::ffi::OsStr;
-use std::io::{self, Write};
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufWriter, IsTerminal, Read, Write};
 use std::path::Path;
+use std::time::Instant;
 
-use crate::action::{ActionKind, ActionRef};
+use crate::action::{ActionKind, ActionRef, Index};
 use crate::commands::{Command, CommandInput};
 use crate::error::{ConfigError, ConfigResult, RuntimeError, RuntimeErrorKind, RuntimeResult};
-use crate::palette::PaletteParser;
-use crate::util::Region;
-use crate::Cli;
+use crate::palette::{self, PaletteParser};
+use crate::util::{self, Region};
+use crate::{Cli, OnReset};
 
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime};
 use clap::{ArgEnum, ArgGroup, Args};
 use image::io::Reader as ImageReader;
 use image::{Pixel, Rgba, RgbaImage};
-use rayon::iter::ParallelIterator;
-use rayon::str::ParallelString;
 
 #[derive(Args)]
 #[clap(
@@ -22,16 +24,17 @@ use rayon::str::ParallelString;
 Guaranted to produce 2 frames per render, where the first frame is the background and the last frame is the complete contents of the log.
 To output only the final result, use the \"--screenshot\" arg or manually skip the first frame \"--skip\"."
 )]
-#[clap(group = ArgGroup::new("step-qol").args(&["step", "skip", "screenshot"]).required(true).multiple(true))]
-#[clap(group = ArgGroup::new("step-qol-conflict").args(&["step", "skip"]).multiple(true).conflicts_with("screenshot"))]
+#[clap(group = ArgGroup::new("step-qol").args(&["step", "skip", "screenshot", "only-final-frame-to-stdout", "realtime-ratio", "daily", "weekly", "reconstruct-at", "compare"]).required(true).multiple(true))]
+#[clap(group = ArgGroup::new("step-qol-conflict").args(&["step", "skip"]).multiple(true).conflicts_with_all(&["screenshot", "only-final-frame-to-stdout"]))]
 #[clap(group = ArgGroup::new("bg-qol").args(&["color", "size", "bg"]).required(true).multiple(true))]
-#[clap(group = ArgGroup::new("bg-qol-conflict").args(&["color", "size"]).multiple(true).conflicts_with("bg"))]
 pub struct RenderInput {
     #[clap(short, long)]
     #[clap(value_name("PATH"))]
-    #[clap(help = "Filepath of input log file")]
+    #[clap(help = "Filepath or url of input log file")]
+    #[clap(long_help = "Filepath or url of input log file [defaults to STDIN when piped]
+Urls are downloaded in full before rendering (Requires the \"url\" feature)")]
     #[clap(display_order = 0)]
-    src: String,
+    src: Option<String>,
     #[clap(short, long)]
     #[clap(value_name("PATH"))]
     #[clap(help = "Filepath of output frames")]
@@ -49,19 +52,33 @@ pub struct RenderInput {
     #[clap(long_help = "Filepath of palette [possible types: .json, .txt, .gpl, .aco, .csv]")]
     #[clap(display_order = 0)]
     palette: Option<String>,
+    #[clap(long)]
+    #[clap(value_name("PATH"))]
+    #[clap(conflicts_with("palette"))]
+    #[clap(help = "Derive the palette from an image")]
+    #[clap(long_help = "Derive the palette from the unique colors of an image (e.g. a canvas snapshot)
+Colors are ordered by frequency so index 0 is the most common, capped at 256 colors and ignoring transparent pixels")]
+    palette_from_image: Option<String>,
     #[clap(long, arg_enum)]
     #[clap(value_name("ENUM"))]
     #[clap(help = "Type of render")]
     style: Option<RenderType>,
-    #[clap(long)]
-    #[clap(value_name("LONG"))]
+    #[clap(long, parse(try_from_str = util::parse_duration))]
+    #[clap(value_name("DURATION"))]
     #[clap(help = "Time or pixels between frames (0 is max)")]
+    #[clap(long_help = "Time or pixels between frames (0 is max), times are milliseconds unless given a unit [e.g. 500, 30s, 5m, 1.5h, 2d or 1w]")]
     step: Option<i64>,
     #[clap(long, arg_enum)]
     #[clap(value_name("ENUM"))]
     #[clap(help = "Whether step represents time or pixels")]
     step_type: Option<StepType>,
     #[clap(long)]
+    #[clap(value_name("FLOAT"))]
+    #[clap(help = "Scale each pixel step by this ratio")]
+    #[clap(long_help = "Scale each pixel step by this ratio (Pixel steps only), frame n (from 0) holds max(1, round(step * ratio^n)) actions
+A ratio below 1 bursts the base image into the first frames and spends later frames on recent activity [defaults to 1]")]
+    pixel_ramp: Option<f64>,
+    #[clap(long)]
     #[clap(value_name("INT"))]
     #[clap(help = "Skip specified frames")]
     skip: Option<usize>,
@@ -70,11 +87,22 @@ pub struct RenderInput {
     #[clap(min_values(2))]
     #[clap(value_name("INT"))]
     #[clap(help = "Size of render")]
+    #[clap(long_help = "Size of render, a background image is placed in the top left corner and any uncovered area is filled with the background color [defaults to transparent]")]
     size: Option<Vec<u32>>,
     #[clap(long)]
     #[clap(help = "Render only final frame")]
     #[clap(long_help = "Render only final frame (Alias of \"--step 0 --skip 1\")")]
     screenshot: bool,
+    #[clap(long)]
+    #[clap(conflicts_with_all(&["step", "step-type", "weekly", "realtime-ratio", "screenshot", "only-final-frame-to-stdout"]))]
+    #[clap(help = "Render one frame per calendar day")]
+    #[clap(long_help = "Render one frame per calendar day showing the canvas at the end of that day, days without actions are still rendered")]
+    daily: bool,
+    #[clap(long)]
+    #[clap(conflicts_with_all(&["step", "step-type", "realtime-ratio", "screenshot", "only-final-frame-to-stdout"]))]
+    #[clap(help = "Render one frame per calendar week")]
+    #[clap(long_help = "Render one frame per calendar week (Monday to Sunday) showing the canvas at the end of that week, weeks without actions are still rendered")]
+    weekly: bool,
     // #[clap(long)]
     // #[clap(value_name("FLOAT"))]
     // #[clap(help = "Opacity of render")]
@@ -93,6 +121,248 @@ pub struct RenderInput {
     #[clap(help = "Region to save")]
     #[clap(long_help = "Region to save (x1, y1, x2, y2)")]
     crop: Vec<u32>,
+    #[clap(long)]
+    #[clap(help = "Pad odd dimensions to be even")]
+    #[clap(long_help = "Pad odd dimensions to be even by extending the right/bottom edge by one pixel (Required by YUV video encoders, the extra pixels only contain the background color)")]
+    pad_to_even: bool,
+    #[clap(long)]
+    #[clap(conflicts_with_all(&["color", "keep-transparency"]))]
+    #[clap(help = "Render only placed pixels over a transparent canvas")]
+    #[clap(long_help = "Render only placed pixels over a fully transparent canvas for compositing, a background image only sets the size
+Destinations without an alpha channel (.jpg, .jpeg) are rejected")]
+    no_background: bool,
+    #[clap(long, parse(try_from_str = util::parse_duration))]
+    #[clap(max_values(3))]
+    #[clap(min_values(3))]
+    #[clap(value_name("DURATION"))]
+    #[clap(help = "Periods of the combined render channels")]
+    #[clap(long_help = "Periods of the combined render channels (red, green, blue), milliseconds unless given a unit [defaults to 1s 1m 1h]")]
+    combined_periods: Option<Vec<i64>>,
+    #[clap(long)]
+    #[clap(help = "Print a legend explaining the render colors")]
+    legend: bool,
+    #[clap(long, parse(try_from_str = util::parse_color))]
+    #[clap(value_name("HEX"))]
+    #[clap(help = "Color of placement renders")]
+    #[clap(long_help = "Color of placement renders (#RRGGBB) [defaults to red, green and blue for milliseconds, seconds and minutes]")]
+    placement_color: Option<[u8; 4]>,
+    #[clap(long, parse(try_from_str = util::parse_duration))]
+    #[clap(value_name("DURATION"))]
+    #[clap(help = "Period of placement renders")]
+    #[clap(long_help = "Period of placement renders, milliseconds unless given a unit [defaults to 1s, 1m and 1h for milliseconds, seconds and minutes]")]
+    placement_period: Option<i64>,
+    #[clap(long, parse(try_from_str = util::parse_color))]
+    #[clap(value_name("HEX"))]
+    #[clap(help = "Color of virgin renders")]
+    #[clap(long_help = "Color of touched pixels in virgin renders (#RRGGBBAA) [defaults to black]")]
+    virgin_color: Option<[u8; 4]>,
+    #[clap(long)]
+    #[clap(value_name("INT"))]
+    #[clap(help = "Maximum number of frames to render")]
+    #[clap(long_help = "Maximum number of frames to render, aborts if exceeded [defaults to 100000]")]
+    max_frames: Option<usize>,
+    #[clap(long)]
+    #[clap(help = "Only render pixels that survive until the end of the log")]
+    #[clap(long_help = "Only render pixels that survive until the end of the log (Normal render only), pixels that are later undone, nuked or rolled back remain as background")]
+    survivors_only: bool,
+    #[clap(long)]
+    #[clap(requires("dst"))]
+    #[clap(help = "Output frames as an animated png")]
+    #[clap(long_help = "Output frames into a single animated png (APNG) rather than a numbered sequence of images")]
+    apng: bool,
+    #[clap(long = "loop")]
+    #[clap(value_name("INT"))]
+    #[clap(requires("apng"))]
+    #[clap(help = "Number of times animated outputs play (0 is infinite)")]
+    #[clap(long_help = "Number of times animated outputs play, 0 loops forever [defaults to 0]")]
+    loop_count: Option<u32>,
+    #[clap(long)]
+    #[clap(requires("dst"))]
+    #[clap(conflicts_with("apng"))]
+    #[clap(help = "Stream raw frame data to destination")]
+    #[clap(long_help = "Stream raw frame data to destination as if it were STDOUT (e.g. a named pipe/FIFO), blocks until a reader is connected")]
+    pipe: bool,
+    #[clap(long, parse(try_from_str = util::parse_timestamp))]
+    #[clap(value_name("TIMESTAMP"))]
+    #[clap(requires("dst"))]
+    #[clap(conflicts_with_all(&["step", "skip", "screenshot", "apng", "pipe"]))]
+    #[clap(help = "Export the canvas at this date as an indexed png")]
+    #[clap(long_help = "Export the palette indices of the canvas at this date as an indexed png [e.g. \"2022-05-01T10:00:00\", \"2022-05-01 10:00\" or \"2022-05-01\"]
+Pixels without placements are transparent, undos are resolved from the placement history within the log")]
+    reconstruct_at: Option<NaiveDateTime>,
+    #[clap(long)]
+    #[clap(value_name("INT"))]
+    #[clap(help = "Delay between frames of animated outputs in milliseconds")]
+    #[clap(long_help = "Delay between frames of animated outputs in milliseconds [defaults to 100]")]
+    frame_delay: Option<u16>,
+    #[clap(long)]
+    #[clap(value_name("PATH"))]
+    #[clap(conflicts_with_all(&["step", "skip", "screenshot", "apng", "pipe", "reconstruct-at"]))]
+    #[clap(help = "Compare the final canvas against a reference image")]
+    #[clap(long_help = "Compare the final canvas against a reference image, printing the percentage of matching pixels and saving a difference image to destination (if provided)
+Reference colors are mapped to the nearest palette color, transparent reference pixels and pixels without placements in the log are ignored
+The difference image marks matching pixels green and differing pixels red")]
+    compare: Option<String>,
+    #[clap(long)]
+    #[clap(value_name("INT"))]
+    #[clap(help = "Only process the first n actions")]
+    #[clap(long_help = "Only process the first n actions of the log, useful for quick previews alongside \"--screenshot\"")]
+    limit: Option<usize>,
+    #[clap(long)]
+    #[clap(requires("bg"))]
+    #[clap(help = "Preserve transparent background pixels")]
+    #[clap(long_help = "Preserve transparent background pixels [by default fully transparent pixels of the background image are replaced with opaque black]")]
+    keep_transparency: bool,
+    #[clap(long)]
+    #[clap(value_name("INT"))]
+    #[clap(allow_hyphen_values(true))]
+    #[clap(help = "Shift log indices before the palette lookup")]
+    #[clap(long_help = "Shift log indices before the palette lookup (Normal render only), indices outside the palette use the background
+Useful when a log and palette come from different canvas versions")]
+    palette_offset: Option<i64>,
+    #[clap(long, parse(try_from_str = util::parse_color))]
+    #[clap(value_name("HEX"))]
+    #[clap(help = "Highlight placements with indices outside the palette")]
+    #[clap(long_help = "Paint placements with indices outside the palette in this color rather than the background (#RRGGBB) (Normal render only)")]
+    highlight_unknown: Option<[u8; 4]>,
+    #[clap(long)]
+    #[clap(value_name("PATH"))]
+    #[clap(requires("dst"))]
+    #[clap(conflicts_with_all(&["apng", "pipe"]))]
+    #[clap(help = "Filepath of a JSON manifest describing the output frames")]
+    #[clap(long_help = "Filepath of a JSON manifest describing the output frames
+Each frame lists its index, filepath, timestamps of its first and last action and the number of actions")]
+    manifest: Option<String>,
+    #[clap(long)]
+    #[clap(requires_all(&["dst", "screenshot"]))]
+    #[clap(conflicts_with_all(&["survivors-only", "palette-offset", "highlight-unknown"]))]
+    #[clap(help = "Save the final frame as an indexed png")]
+    #[clap(long_help = "Save the final frame as an indexed png using the palette, far smaller than RGBA output
+Only supported by palette based renders (Normal), pixels without placements are transparent rather than the background")]
+    indexed: bool,
+    #[clap(long, parse(try_from_str = parse_action_color))]
+    #[clap(multiple_occurrences(true))]
+    #[clap(value_name("KIND=HEX"))]
+    #[clap(help = "Color of an action kind in action renders")]
+    #[clap(long_help = "Color of an action kind in action renders (e.g. \"undo=#FF00FF\"), may be repeated
+[defaults to place=#0000FF, undo=#FF00FF, overwrite=#00FFFF, rollback=#00FF00, rollback-undo=#FFFF00, nuke=#FF0000]")]
+    action_color: Vec<(ActionKind, [u8; 4])>,
+    #[clap(long)]
+    #[clap(conflicts_with_all(&["dst", "screenshot"]))]
+    #[clap(help = "Write only the final frame to STDOUT with a size header")]
+    #[clap(long_help = "Write only the final frame to STDOUT as raw RGBA, preceded by a 16 byte header so readers need not know the dimensions
+Header: magic \"PXLR\", width (u32 LE), height (u32 LE), format \"RGBA\"")]
+    only_final_frame_to_stdout: bool,
+    #[clap(long)]
+    #[clap(conflicts_with_all(&["apng", "manifest"]))]
+    #[clap(help = "Prefix each raw frame with its length")]
+    #[clap(long_help = "Prefix each raw frame written to STDOUT or a pipe with its length in bytes (u32 LE), so readers can split the stream without knowing the dimensions")]
+    framed_raw: bool,
+    #[clap(long)]
+    #[clap(help = "Flush raw output after every frame")]
+    #[clap(long_help = "Flush raw output (STDOUT or \"--pipe\") after every frame so live readers receive frames immediately, otherwise output is written in frame sized chunks which is faster when redirecting to a file")]
+    flush_per_frame: bool,
+    #[clap(long)]
+    #[clap(value_name("PATH"))]
+    #[clap(help = "Filepath of per pixel placement counts")]
+    #[clap(long_help = "Filepath of per pixel placement counts (Activity render only)
+A .csv extension writes \"x,y,count\" rows for every touched pixel, otherwise a binary grid is written:
+magic \"PXLD\", width (u32 LE), height (u32 LE), then a count (u32 LE) per pixel in row order")]
+    density_out: Option<String>,
+    #[clap(long)]
+    #[clap(help = "Interpolate colors in linear light")]
+    #[clap(long_help = "Interpolate colors in linear light rather than sRGB, avoiding muddy midpoints (Milliseconds, seconds, minutes and age renders only)")]
+    gamma_correct: bool,
+    #[clap(long)]
+    #[clap(value_name("PATH"))]
+    #[clap(help = "Filepath of a JSON file describing the rendered region")]
+    #[clap(long_help = "Filepath of a JSON file describing the rendered region within the full canvas
+Contains the bounds (x1, y1, x2, y2, exclusive end), offset and size of the output, useful to reassemble cropped renders")]
+    write_bounds: Option<String>,
+    #[clap(long)]
+    #[clap(value_name("PATH"))]
+    #[clap(help = "Filepath to write the actions being rendered to")]
+    #[clap(long_help = "Filepath to write the actions being rendered to as a log, after parsing, cropping and ordering by time
+Useful to check how a log was parsed, or to normalise a log")]
+    dump_actions: Option<String>,
+    #[clap(long)]
+    #[clap(help = "Fade heat through a multi color gradient")]
+    #[clap(long_help = "Fade heat from white through yellow, red and blue to black over the heat window rather than a single red ramp (Heat render only)")]
+    heat_gradient: bool,
+    #[clap(long)]
+    #[clap(value_name("FLOAT"))]
+    #[clap(requires("fps"))]
+    #[clap(conflicts_with_all(&["step", "step-type", "screenshot", "only-final-frame-to-stdout"]))]
+    #[clap(help = "Canvas time shown per second of output")]
+    #[clap(long_help = "Canvas time shown per second of output, relative to real time (e.g. 3600 shows an hour per second)
+Each frame steps \"ratio * 1000 / fps\" milliseconds, so the output lasts \"span / ratio\" seconds and has \"span / ratio * fps\" frames (span in seconds)")]
+    realtime_ratio: Option<f64>,
+    #[clap(long)]
+    #[clap(value_name("INT"))]
+    #[clap(requires("realtime-ratio"))]
+    #[clap(help = "Frames per second of output")]
+    #[clap(long_help = "Frames per second of output, used with \"--realtime-ratio\" [sets the delay between frames of animated outputs unless \"--frame-delay\" is provided]")]
+    fps: Option<u16>,
+    #[clap(long)]
+    #[clap(help = "Draw a graph of cumulative placements into each frame")]
+    #[clap(long_help = "Draw a small graph of cumulative actions over the span of the log into the bottom left corner of each frame, growing with the timelapse")]
+    graph_overlay: bool,
+    #[clap(long)]
+    #[clap(help = "Omit frames without any actions")]
+    #[clap(long_help = "Omit frames without any actions, animated outputs show the previous frame for longer instead")]
+    skip_empty: bool,
+    #[clap(long, arg_enum)]
+    #[clap(value_name("ENUM"))]
+    #[clap(conflicts_with("skip-empty"))]
+    #[clap(help = "How frames without any actions are shown [defaults to repeat]")]
+    #[clap(long_help = "How frames without any actions are shown [defaults to repeat]
+repeat shows the previous frame again and crossfade blends from the previous to the next frame across the gap
+Only affects multi frame outputs, crossfading renders each following frame early and blends every empty frame so costs more")]
+    fill_gaps: Option<FillGaps>,
+    #[clap(long, parse(try_from_str = util::parse_duration))]
+    #[clap(value_name("DURATION"))]
+    #[clap(conflicts_with_all(&["screenshot", "only-final-frame-to-stdout", "compare"]))]
+    #[clap(help = "Time to show the first frame for")]
+    #[clap(long_help = "Time to show the first frame for, milliseconds unless given a unit [e.g. 500, 30s, 5m or 1h]
+Animated outputs extend its delay (whole seconds beyond 65s) while other outputs repeat it (rounded up to whole frames)")]
+    start_hold: Option<i64>,
+    #[clap(long, parse(try_from_str = util::parse_duration))]
+    #[clap(value_name("DURATION"))]
+    #[clap(conflicts_with_all(&["screenshot", "only-final-frame-to-stdout", "compare"]))]
+    #[clap(help = "Time to show the final frame for")]
+    #[clap(long_help = "Time to show the final frame for, milliseconds unless given a unit [e.g. 500, 30s, 5m or 1h]
+Animated outputs extend its delay (whole seconds beyond 65s) while other outputs repeat it (rounded up to whole frames)")]
+    end_hold: Option<i64>,
+    #[clap(long)]
+    #[clap(help = "Draw borders between territories of different users")]
+    #[clap(long_help = "Draw black borders on pixels next to pixels of a different user (Owner render only)")]
+    territory_borders: bool,
+    #[clap(long, arg_enum)]
+    #[clap(value_name("ENUM"))]
+    #[clap(help = "Handling of actions outside the canvas [defaults to skip]")]
+    #[clap(long_help = "Handling of actions outside the canvas [defaults to skip]
+skip drops them (reporting how many), clamp moves them to the nearest edge pixel and error aborts the render")]
+    oob_policy: Option<OobPolicy>,
+    #[clap(long, arg_enum)]
+    #[clap(multiple_values(true))]
+    #[clap(use_value_delimiter(true))]
+    #[clap(value_name("ENUM"))]
+    #[clap(help = "Only count these action kinds in activity renders")]
+    #[clap(long_help = "Only count these action kinds in activity renders (e.g. \"nuke\" shows where nukes landed), several kinds are combined into one map
+Also applies to \"--density-out\"")]
+    kind_heatmap: Vec<ActionKind>,
+    #[clap(long)]
+    #[clap(help = "Dither gradient renders to reduce banding")]
+    #[clap(long_help = "Dither gradient renders with a 4x4 ordered (Bayer) pattern to reduce banding, nudging each pixel's gradient value by a small position dependent offset
+Deterministic between runs (Activity, heat, milliseconds, seconds, minutes and age renders only)")]
+    dither: bool,
+    #[clap(long, parse(try_from_str = parse_user_color))]
+    #[clap(multiple_occurrences(true))]
+    #[clap(value_name("USER=HEX"))]
+    #[clap(help = "Color of a user in owner renders")]
+    #[clap(long_help = "Color of a user (username or hash) in owner renders (e.g. \"alice=#00FF00\"), may be repeated
+Other users are colored by a hash of their name")]
+    user_color: Vec<(String, [u8; 4])>,
 }
 
 // TODO: Clean
@@ -131,24 +401,65 @@ pub const DEFAULT_PALETTE: [[u8; 4]; 32] = [
     [116, 12, 0, 255],    // Maroon
 ];
 
+#[derive(Clone)]
 pub struct RenderData {
-    src: String,
+    src: Option<String>,
     dst: Option<String>,
     crop: Region<u32>,
     background: RgbaImage,
     style: RenderType,
     step: i64,
     step_type: StepType,
+    pixel_ramp: f64,
     skip: usize,
     palette: Vec<[u8; 4]>,
+    combined_periods: [i64; 3],
+    legend: bool,
+    placement_color: Option<Rgba<u8>>,
+    placement_period: Option<i64>,
+    virgin_color: Rgba<u8>,
+    max_frames: usize,
+    survivors_only: bool,
+    apng: bool,
+    loop_count: u32,
+    pipe: bool,
+    reconstruct_at: Option<NaiveDateTime>,
+    frame_delay: u16,
+    reference: Option<RgbaImage>,
+    limit: Option<usize>,
+    palette_offset: i64,
+    highlight_unknown: Option<Rgba<u8>>,
+    manifest: Option<String>,
+    indexed: bool,
+    action_colors: Vec<(ActionKind, Rgba<u8>)>,
+    raw_header: bool,
+    framed_raw: bool,
+    flush_per_frame: bool,
+    density_out: Option<String>,
+    gamma_correct: bool,
+    write_bounds: Option<String>,
+    dump_actions: Option<String>,
+    heat_gradient: bool,
+    graph_overlay: bool,
+    skip_empty: bool,
+    fill_gaps: FillGaps,
+    start_hold: i64,
+    end_hold: i64,
+    territory_borders: bool,
+    user_colors: HashMap<String, Rgba<u8>>,
+    oob_policy: OobPolicy,
+    kind_heatmap: Vec<ActionKind>,
+    dither: bool,
 }
 
 impl CommandInput<RenderData> for RenderInput {
     fn validate(&self) -> ConfigResult<RenderData> {
-        let palette = match &self.palette {
-            Some(path) => PaletteParser::try_parse(&path)
+        let palette = match (&self.palette, &self.palette_from_image) {
+            (Some(path), _) => PaletteParser::try_parse(&path)
                 .map_err(|e| ConfigError::new("palette", &e.to_string()))?,
-            None => DEFAULT_PALETTE.to_vec(),
+            (None, Some(path)) => PaletteParser::try_parse_image(path)
+                .map_err(|e| ConfigError::new("palette_from_image", &e.to_string()))?,
+            (None, None) => DEFAULT_PALETTE.to_vec(),
         };
 
         let mut step = self.step.unwrap_or(i64::MAX);
@@ -156,10 +467,53 @@ impl CommandInput<RenderData> for RenderInput {
             step = i64::MAX;
         }
 
-        let step_type = self.step_type.unwrap_or_default();
+        let fps = match self.fps {
+            Some(0) => Err(ConfigError::new("fps", "must be positive"))?,
+            fps => fps,
+        };
+        if let (Some(ratio), Some(fps)) = (self.realtime_ratio, fps) {
+            // Milliseconds of canvas time per frame
+            let ms = ratio * SECOND.num_milliseconds() as f64 / fps as f64;
+            if !ms.is_finite() || ms < 1.0 {
+                Err(ConfigError::new(
+                    "realtime_ratio",
+                    "must step at least 1 millisecond per frame",
+                ))?
+            }
+            step = ms.round() as i64;
+        }
+
+        let step_type = match (self.daily, self.weekly) {
+            (true, _) => StepType::Daily,
+            (_, true) => StepType::Weekly,
+            _ => self.step_type.unwrap_or_default(),
+        };
+
+        if let Some(ratio) = self.pixel_ramp {
+            if !matches!(step_type, StepType::Pixels) {
+                Err(ConfigError::new("pixel_ramp", "only supported by pixel steps"))?
+            }
+            if !(ratio.is_finite() && ratio > 0.0) {
+                Err(ConfigError::new("pixel_ramp", "ratio must be positive"))?
+            }
+        }
+
+        // Steps aren't used as the delay, a step of minutes would show each frame for over a minute
+        let frame_delay = match (self.frame_delay, fps) {
+            (Some(delay), _) => delay,
+            (None, Some(fps)) => (SECOND.num_milliseconds() / fps as i64) as u16,
+            (None, None) => DEFAULT_FRAME_DELAY,
+        };
+
+        if self.start_hold.unwrap_or(0) < 0 {
+            Err(ConfigError::new("start_hold", "must not be negative"))?
+        }
+        if self.end_hold.unwrap_or(0) < 0 {
+            Err(ConfigError::new("end_hold", "must not be negative"))?
+        }
 
         let mut skip = self.skip.unwrap_or(0);
-        if self.screenshot {
+        if self.screenshot || self.only_final_frame_to_stdout {
             skip = 1;
         }
 
@@ -171,27 +525,231 @@ impl CommandInput<RenderData> for RenderInput {
             },
         };
 
+        let combined_periods = match &self.combined_periods {
+            Some(periods) => {
+                if periods.iter().any(|p| *p <= 0) {
+                    Err(ConfigError::new("combined_periods", "periods must be positive"))?
+                }
+                [periods[0], periods[1], periods[2]]
+            }
+            None => [SECOND, MINUTE, HOUR].map(|period| period.num_milliseconds()),
+        };
+
+        if let Some(period) = self.placement_period {
+            if period <= 0 {
+                Err(ConfigError::new("placement_period", "period must be positive"))?
+            }
+        }
+
+        if self.src.is_none() && io::stdin().is_terminal() {
+            Err(ConfigError::new("src", "required unless a log is piped to STDIN"))?
+        }
+
+        if self.limit == Some(0) {
+            Err(ConfigError::new("limit", "limit must be positive"))?
+        }
+
+        let style = self.style.unwrap_or(RenderType::Normal);
+        if self.survivors_only && !matches!(style, RenderType::Normal) {
+            Err(ConfigError::new("survivors_only", "only supported by normal render"))?
+        }
+        if self.palette_offset.is_some() && !matches!(style, RenderType::Normal) {
+            Err(ConfigError::new("palette_offset", "only supported by normal render"))?
+        }
+        if self.highlight_unknown.is_some() && !matches!(style, RenderType::Normal) {
+            Err(ConfigError::new("highlight_unknown", "only supported by normal render"))?
+        }
+        if !self.action_color.is_empty() && !matches!(style, RenderType::Action) {
+            Err(ConfigError::new("action_color", "only supported by action render"))?
+        }
+        if !self.user_color.is_empty() && !matches!(style, RenderType::Owner) {
+            Err(ConfigError::new("user_color", "only supported by owner render"))?
+        }
+        if self.territory_borders && !matches!(style, RenderType::Owner) {
+            Err(ConfigError::new("territory_borders", "only supported by owner render"))?
+        }
+        if self.heat_gradient && !matches!(style, RenderType::Heat) {
+            Err(ConfigError::new("heat_gradient", "only supported by heat render"))?
+        }
+        if !self.kind_heatmap.is_empty() && !matches!(style, RenderType::Activity) {
+            Err(ConfigError::new("kind_heatmap", "only supported by activity render"))?
+        }
+        if self.density_out.is_some() && !matches!(style, RenderType::Activity) {
+            Err(ConfigError::new("density_out", "only supported by activity render"))?
+        }
+        if self.gamma_correct
+            && !matches!(
+                style,
+                RenderType::Milliseconds | RenderType::Seconds | RenderType::Minutes | RenderType::Age
+            )
+        {
+            Err(ConfigError::new(
+                "gamma_correct",
+                "only supported by milliseconds, seconds, minutes and age renders",
+            ))?
+        }
+        if self.dither
+            && !matches!(
+                style,
+                RenderType::Activity
+                    | RenderType::Heat
+                    | RenderType::Milliseconds
+                    | RenderType::Seconds
+                    | RenderType::Minutes
+                    | RenderType::Age
+            )
+        {
+            Err(ConfigError::new(
+                "dither",
+                "only supported by activity, heat, milliseconds, seconds, minutes and age renders",
+            ))?
+        }
+        if self.framed_raw && self.dst.is_some() && !self.pipe {
+            Err(ConfigError::new(
+                "framed_raw",
+                "only supported by raw output (STDOUT or \"--pipe\")",
+            ))?
+        }
+        if self.flush_per_frame && self.dst.is_some() && !self.pipe {
+            Err(ConfigError::new(
+                "flush_per_frame",
+                "only supported by raw output (STDOUT or \"--pipe\")",
+            ))?
+        }
+        if self.no_background {
+            let is_opaque = self
+                .dst
+                .as_deref()
+                .map(Path::new)
+                .and_then(Path::extension)
+                .is_some_and(|e| {
+                    e.eq_ignore_ascii_case("jpg") || e.eq_ignore_ascii_case("jpeg")
+                });
+            if is_opaque {
+                Err(ConfigError::new("no_background", "destination cannot be transparent"))?
+            }
+        }
+        if self.indexed {
+            if !matches!(style, RenderType::Normal) {
+                Err(ConfigError::new("indexed", "only supported by normal render"))?
+            }
+            let is_png = self
+                .dst
+                .as_deref()
+                .map(Path::new)
+                .and_then(Path::extension)
+                .is_some_and(|e| e.eq_ignore_ascii_case("png"));
+            if !is_png {
+                Err(ConfigError::new("indexed", "destination must be a .png"))?
+            }
+            if palette.len() > u8::MAX as usize {
+                Err(ConfigError::new(
+                    "indexed",
+                    "requires at most 255 palette colors (one slot is reserved for transparency)",
+                ))?
+            }
+        }
+
         let crop = Region::from_slice(&self.crop).unwrap_or(Region::all());
         let background = match &self.bg {
-            Some(path) => get_background(path, &crop, self.dst.is_none())
-                .map_err(|e| RuntimeError::from_err(e, path, 0))
-                .map_err(|e| ConfigError::new("bg", &e.to_string()))?, // TODO: Mapping but better?
+            Some(path) => {
+                let background = get_background(path, &crop, self.keep_transparency)
+                    .map_err(|e| RuntimeError::from_err(e, path, 0))
+                    .map_err(|e| ConfigError::new("bg", &e.to_string()))?; // TODO: Mapping but better?
+                match &self.size {
+                    Some(size) => {
+                        let fill = match &self.color {
+                            Some(color) => *Rgba::from_slice(color),
+                            None => Rgba::from([0, 0, 0, 0]),
+                        };
+                        resize_canvas(background, size[0], size[1], fill)
+                    }
+                    None => background,
+                }
+            }
             None => match &self.size {
                 Some(size) => RgbaImage::from_pixel(size[0], size[1], color),
                 None => Err(ConfigError::new("bg", "cannot infer size"))?,
             },
         };
+        let background = match self.pad_to_even {
+            true => pad_to_even(background, color),
+            false => background,
+        };
+        let background = match self.no_background {
+            true => RgbaImage::new(background.width(), background.height()),
+            false => background,
+        };
+
+        let reference = match &self.compare {
+            Some(path) => {
+                let reference = get_background(path, &crop, true)
+                    .map_err(|e| RuntimeError::from_err(e, path, 0))
+                    .map_err(|e| ConfigError::new("compare", &e.to_string()))?;
+                if reference.dimensions() != background.dimensions() {
+                    Err(ConfigError::new("compare", "size does not match render"))?
+                }
+                Some(reference)
+            }
+            None => None,
+        };
 
         Ok(RenderData {
             src: self.src.to_owned(),
             dst: self.dst.to_owned(),
             crop,
             background,
-            style: self.style.unwrap_or(RenderType::Normal),
+            style,
             step,
             step_type,
+            pixel_ramp: self.pixel_ramp.unwrap_or(1.0),
             skip,
             palette,
+            combined_periods,
+            legend: self.legend,
+            placement_color: self.placement_color.map(Rgba::from),
+            placement_period: self.placement_period,
+            virgin_color: Rgba::from(self.virgin_color.unwrap_or([0, 0, 0, 255])),
+            max_frames: self.max_frames.unwrap_or(100000),
+            survivors_only: self.survivors_only,
+            apng: self.apng,
+            loop_count: self.loop_count.unwrap_or(0),
+            pipe: self.pipe,
+            reconstruct_at: self.reconstruct_at,
+            frame_delay,
+            reference,
+            limit: self.limit,
+            palette_offset: self.palette_offset.unwrap_or(0),
+            highlight_unknown: self.highlight_unknown.map(Rgba::from),
+            manifest: self.manifest.to_owned(),
+            indexed: self.indexed,
+            action_colors: self
+                .action_color
+                .iter()
+                .map(|(kind, color)| (*kind, Rgba::from(*color)))
+                .collect(),
+            raw_header: self.only_final_frame_to_stdout,
+            framed_raw: self.framed_raw,
+            flush_per_frame: self.flush_per_frame,
+            density_out: self.density_out.to_owned(),
+            gamma_correct: self.gamma_correct,
+            write_bounds: self.write_bounds.to_owned(),
+            dump_actions: self.dump_actions.to_owned(),
+            heat_gradient: self.heat_gradient,
+            graph_overlay: self.graph_overlay,
+            skip_empty: self.skip_empty,
+            fill_gaps: self.fill_gaps.unwrap_or_default(),
+            start_hold: self.start_hold.unwrap_or(0),
+            end_hold: self.end_hold.unwrap_or(0),
+            territory_borders: self.territory_borders,
+            kind_heatmap: self.kind_heatmap.to_owned(),
+            dither: self.dither,
+            user_colors: self
+                .user_color
+                .iter()
+                .map(|(user, color)| (user.to_owned(), Rgba::from(*color)))
+                .collect(),
+            oob_policy: self.oob_policy.unwrap_or_default(),
         })
     }
 }
@@ -201,10 +759,13 @@ fn get_background(path: &str, crop: &Region<u32>, transparent: bool) -> RuntimeR
     let y = crop.start().1;
     let width = crop.width();
     let height = crop.height();
-    let mut out = ImageReader::open(path)?
-        .decode()?
-        .crop_imm(x, y, width, height)
-        .to_rgba8();
+    let image = ImageReader::open(path)?.decode()?.into_rgba8();
+
+    // RGBA images that need no crop are used as is rather than copied
+    let mut out = match x == 0 && y == 0 && width >= image.width() && height >= image.height() {
+        true => image,
+        false => image::imageops::crop_imm(&image, x, y, width, height).to_image(),
+    };
 
     // Remove transparency
     if !transparent {
@@ -216,6 +777,33 @@ fn get_background(path: &str, crop: &Region<u32>, transparent: bool) -> RuntimeR
     Ok(out)
 }
 
+fn pad_to_even(background: RgbaImage, color: Rgba<u8>) -> RgbaImage {
+    let width = background.width() + background.width() % 2;
+    let height = background.height() + background.height() % 2;
+    resize_canvas(background, width, height, color)
+}
+
+// Places the background in the top left corner of a canvas filled with color
+fn resize_canvas(background: RgbaImage, width: u32, height: u32, color: Rgba<u8>) -> RgbaImage {
+    if width == background.width() && height == background.height() {
+        return background;
+    }
+
+    let mut out = RgbaImage::from_pixel(width, height, color);
+    image::imageops::replace(&mut out, &background, 0, 0);
+    out
+}
+
+// Delay between frames of animated outputs (milliseconds)
+const DEFAULT_FRAME_DELAY: u16 = 100;
+
+enum Destination<'a> {
+    Frames(&'a str),
+    Apng(&'a str, png::Writer<BufWriter<File>>),
+    Pipe(&'a str, BufWriter<File>),
+    Stdout(BufWriter<io::Stdout>),
+}
+
 #[derive(Debug, Copy, Clone, ArgEnum)]
 enum RenderType {
     Normal,
@@ -228,6 +816,7 @@ enum RenderType {
     Minutes,
     Combined,
     Age,
+    Owner,
 }
 
 impl Default for RenderType {
@@ -236,10 +825,48 @@ impl Default for RenderType {
     }
 }
 
+impl RenderType {
+    // Period of placement renders unless provided
+    fn default_period(&self) -> Duration {
+        match self {
+            RenderType::Milliseconds => SECOND,
+            RenderType::Seconds => MINUTE,
+            _ => HOUR,
+        }
+    }
+}
+
+const SECOND: Duration = Duration::seconds(1);
+const MINUTE: Duration = Duration::minutes(1);
+const HOUR: Duration = Duration::hours(1);
+
+// Pixels placed longer ago than this are cold in heat renders
+const HEAT_WINDOW: Duration = Duration::minutes(15);
+
+#[derive(Debug, Default, Copy, Clone, ArgEnum)]
+enum OobPolicy {
+    #[default]
+    Skip,
+    Clamp,
+    Error,
+}
+
+#[derive(Debug, Default, Copy, Clone, ArgEnum)]
+enum FillGaps {
+    #[default]
+    Repeat,
+    Crossfade,
+}
+
 #[derive(Debug, Copy, Clone, ArgEnum)]
 enum StepType {
     Time,
     Pixels,
+    // Set by "--daily" and "--weekly"
+    #[clap(skip)]
+    Daily,
+    #[clap(skip)]
+    Weekly,
 }
 
 impl Default for StepType {
@@ -250,100 +877,541 @@ impl Default for StepType {
 
 trait Renderable {
     fn render(&mut self, actions: &[ActionRef], frame: &mut RgbaImage);
+    fn legend(&self) -> Option<String> {
+        None
+    }
 }
 
 impl Command for RenderData {
     fn run(&self, settings: &Cli) -> RuntimeResult<()> {
-        let stdout = io::stdout();
-
         // TODO: Clobber
         assert!(!settings.noclobber);
 
-        let data = std::fs::read_to_string(&self.src)
-            .map_err(|e| RuntimeError::from_err(e, &self.src, 0))?;
-        let pixels: Vec<ActionRef> = data
-            .as_parallel_string()
-            .par_lines()
-            .filter_map(|s| match ActionRef::try_from(s) {
-                Ok(mut a) => {
-                    if self.crop.contains(a.x, a.y) {
-                        a.x -= self.crop.start().0;
-                        a.y -= self.crop.start().1;
-                        Some(a)
-                    } else {
-                        None
-                    }
+        let (data, src) = match &self.src {
+            Some(path) => (
+                util::read_log(path).map_err(|e| RuntimeError::from_err(e, path, 0))?,
+                path.as_str(),
+            ),
+            None => {
+                let mut data = String::new();
+                io::stdin().lock().read_to_string(&mut data)?;
+                (data, "STDIN")
+            }
+        };
+        let pixels = super::parse_actions(&data, src, settings, self.limit)?;
+        // Filter and stats report empty input as empty output instead
+        if pixels.is_empty() {
+            Err(RuntimeError::new_with_file(RuntimeErrorKind::NoActions, src, 0))?;
+        }
+        match settings.on_reset {
+            Some(OnReset::Split) => {
+                let segments = super::split_resets(&pixels);
+                if segments.len() == 1 {
+                    return self.render_canvas(pixels, src, settings);
                 }
-                Err(_) => None, // TODO
-            })
-            .collect();
+                for (n, segment) in segments.iter().enumerate() {
+                    self.for_canvas(n).render_canvas(segment.to_vec(), src, settings)?;
+                }
+                Ok(())
+            }
+            _ => self.render_canvas(pixels, src, settings),
+        }
+    }
+}
+
+impl RenderData {
+    fn render_canvas(
+        &self,
+        mut pixels: Vec<ActionRef>,
+        src: &str,
+        settings: &Cli,
+    ) -> RuntimeResult<()> {
+        pixels.retain(|a| self.crop.contains(a.x, a.y));
+        // Last writer wins by timestamp, stable sort keeps log order for equal timestamps
+        pixels.sort_by_key(|a| a.time);
+        if let Some(path) = &self.dump_actions {
+            let mut out = String::new();
+            for action in &pixels {
+                action.to_string_into(&mut out);
+            }
+            std::fs::write(path, out).map_err(|e| RuntimeError::from_err(e, path, 0))?;
+        }
+        for action in pixels.iter_mut() {
+            action.x -= self.crop.start().0;
+            action.y -= self.crop.start().1;
+        }
+
+        let width = self.background.width();
+        let height = self.background.height();
+        self.handle_out_of_bounds(&mut pixels, width, height)?;
 
         if pixels.is_empty() {
             Err(RuntimeError::new_with_file(
                 RuntimeErrorKind::UnexpectedEof,
-                &self.src,
+                src,
                 0,
             ))?;
         }
 
-        let width = self.background.width();
-        let height = self.background.height();
+        if let Some(path) = &self.write_bounds {
+            let (x, y) = self.crop.start();
+            let bounds = serde_json::json!({
+                "x1": x,
+                "y1": y,
+                "x2": x + width,
+                "y2": y + height,
+                "offset": [x, y],
+                "width": width,
+                "height": height,
+            });
+            let file = File::create(path).map_err(|e| RuntimeError::from_err(e, path, 0))?;
+            serde_json::to_writer_pretty(BufWriter::new(file), &bounds)
+                .map_err(|e| RuntimeError::from_err(e, path, 0))?;
+        }
+        if let Some(reference) = &self.reference {
+            // Safe unwrap (pixels.len > 0)
+            let at = pixels.last().unwrap().time;
+            let canvas = reconstruct_canvas(&pixels, at, width, height);
+            return self.compare(&canvas, reference);
+        }
+        if let (Some(at), Some(path)) = (self.reconstruct_at, &self.dst) {
+            let canvas = reconstruct_canvas(&pixels, at, width, height);
+            return Self::canvas_to_png(&canvas, &self.palette, width, height, path)
+                .map_err(|e| RuntimeError::from_err(e, path, 0));
+        }
+        if let (true, Some(path)) = (self.indexed, &self.dst) {
+            // Safe unwrap (pixels.len > 0)
+            let at = pixels.last().unwrap().time;
+            let canvas = reconstruct_canvas(&pixels, at, width, height);
+            return Self::frame_path(path, 0)
+                .and_then(|frame_path| {
+                    Self::canvas_to_png(&canvas, &self.palette, width, height, &frame_path)
+                })
+                .map_err(|e| RuntimeError::from_err(e, path, 0));
+        }
+
         let mut renderer: Box<dyn Renderable> = match self.style {
-            RenderType::Normal => Box::new(NormalRender::new(&self.background, &self.palette)),
-            RenderType::Activity => Box::new(ActivityRender::new(width, height)),
-            RenderType::Heat => Box::new(HeatRender::new(width, height, self.step)),
-            RenderType::Virgin => Box::new(VirginRender {}),
-            RenderType::Action => Box::new(ActionRender {}),
-            RenderType::Combined => Box::new(CombinedRender {}),
+            RenderType::Normal => {
+                let survivors = match self.survivors_only {
+                    true => Some(get_survivors(&pixels)),
+                    false => None,
+                };
+                Box::new(NormalRender::new(
+                    &self.background,
+                    &self.palette,
+                    self.palette_offset,
+                    self.highlight_unknown,
+                    survivors,
+                ))
+            }
+            RenderType::Activity => {
+                Box::new(ActivityRender::new(width, height, &self.kind_heatmap, self.dither))
+            }
+            RenderType::Heat => {
+                Box::new(HeatRender::new(
+                    width,
+                    height,
+                    self.step,
+                    self.step_type,
+                    self.heat_gradient,
+                    self.dither,
+                ))
+            }
+            RenderType::Virgin => Box::new(VirginRender::new(self.virgin_color)),
+            RenderType::Action => Box::new(ActionRender::new(&self.action_colors)),
+            RenderType::Combined => Box::new(CombinedRender::new(self.combined_periods)),
             RenderType::Milliseconds => {
-                let bg_color = Rgba::from([255, 0, 0, 255]);
-                Box::new(PlacementRender::new(bg_color, 1000))
+                let bg_color = self.placement_color.unwrap_or(Rgba::from([255, 0, 0, 255]));
+                let period = self
+                    .placement_period
+                    .unwrap_or_else(|| self.style.default_period().num_milliseconds());
+                Box::new(PlacementRender::new(bg_color, period, self.gamma_correct, self.dither))
             }
             RenderType::Seconds => {
-                let bg_color = Rgba::from([0, 255, 0, 255]);
-                Box::new(PlacementRender::new(bg_color, 60000))
+                let bg_color = self.placement_color.unwrap_or(Rgba::from([0, 255, 0, 255]));
+                let period = self
+                    .placement_period
+                    .unwrap_or_else(|| self.style.default_period().num_milliseconds());
+                Box::new(PlacementRender::new(bg_color, period, self.gamma_correct, self.dither))
             }
             RenderType::Minutes => {
-                let bg_color = Rgba::from([0, 0, 255, 255]);
-                Box::new(PlacementRender::new(bg_color, 3600000))
+                let bg_color = self.placement_color.unwrap_or(Rgba::from([0, 0, 255, 255]));
+                let period = self
+                    .placement_period
+                    .unwrap_or_else(|| self.style.default_period().num_milliseconds());
+                Box::new(PlacementRender::new(bg_color, period, self.gamma_correct, self.dither))
             }
             RenderType::Age => {
                 // Safe unwrap (pixels.len > 0)
                 let min = pixels.first().unwrap().time.timestamp_millis();
                 let max = pixels.last().unwrap().time.timestamp_millis();
-                Box::new(AgeRender::new(min, max))
+                Box::new(AgeRender::new(min, max, self.gamma_correct, self.dither))
             }
+            RenderType::Owner => Box::new(OwnerRender::new(
+                &self.background,
+                &self.user_colors,
+                self.territory_borders,
+            )),
         };
 
-        let frames = Self::get_frame_slices(&pixels, self.step, self.step_type);
+        if self.legend {
+            match renderer.legend() {
+                Some(legend) => eprintln!("{}", legend),
+                None => eprintln!("No legend available for {:?} render", self.style),
+            }
+        }
+
+        let frames = Self::get_frame_slices(
+            &pixels,
+            self.step,
+            self.step_type,
+            self.pixel_ramp,
+            self.max_frames,
+        )?;
+        if self.skip >= frames.len() {
+            Err(RuntimeError::new(RuntimeErrorKind::SkipLimit(frames.len())))?;
+        }
         let mut current = self.background.clone();
 
+        let total = match self.skip_empty {
+            true => frames.iter().skip(self.skip).filter(|f| f.is_some()).count(),
+            false => frames.len().saturating_sub(self.skip),
+        };
         if settings.verbose {
-            eprintln!("Rendering {} frames", frames.len());
+            eprintln!("{}", self.summary(total, frames.len() - total));
+        }
+        // Raw outputs buffer a whole frame (and its length prefix) per write
+        let capacity = current.as_raw().len() + 4;
+        let mut dst = match &self.dst {
+            Some(path) if self.apng => Destination::Apng(
+                path,
+                Self::create_apng(path, &current, total, self.frame_delay, self.loop_count)
+                    .map_err(|e| RuntimeError::from_err(e, path, 0))?,
+            ),
+            Some(path) if self.pipe => Destination::Pipe(
+                path,
+                OpenOptions::new()
+                    .write(true)
+                    .open(path)
+                    .map(|file| BufWriter::with_capacity(capacity, file))
+                    .map_err(|e| RuntimeError::from_err(e, path, 0))?,
+            ),
+            Some(path) => Destination::Frames(path),
+            None => Destination::Stdout(BufWriter::with_capacity(capacity, io::stdout())),
+        };
+
+        if let (true, Destination::Stdout(stdout)) = (self.raw_header, &mut dst) {
+            Self::write_raw_header(&current, stdout)
+                .map_err(|e| RuntimeError::from_err(e, "STDOUT", 0))?;
         }
 
         // Render frames
-        for (i, frame) in frames[self.skip..].iter().enumerate() {
+        // Skipped frames are still rendered so later frames contain their actions
+        let mut manifest = vec![];
+        let graph = match self.graph_overlay {
+            true => Some(GraphOverlay::new(&pixels, width, height)),
+            false => None,
+        };
+        let mut latest = None;
+        let mut overlaid = RgbaImage::new(0, 0);
+        let start = Instant::now();
+        let mut written = 0;
+        let mut shown_frames = 0;
+        let mut keyframe = 0;
+        let mut lookahead: Option<(usize, RgbaImage)> = None;
+        let mut faded = RgbaImage::new(0, 0);
+        for (i, frame) in frames.iter().enumerate() {
             if let Some(frame) = frame {
-                current = current.clone();
-                renderer.render(frame, &mut current);
+                match lookahead.take() {
+                    Some((j, next)) if j == i => current = next,
+                    _ => {
+                        current = current.clone();
+                        renderer.render(frame, &mut current);
+                    }
+                }
+                keyframe = i;
+                latest = frame.last().map(|a| a.time);
+            }
+
+            if i < self.skip || (self.skip_empty && frame.is_none()) {
+                continue;
+            }
+
+            // Empty frames fade towards the next frame, which is rendered ahead of time
+            let current = match (self.fill_gaps, frame) {
+                (FillGaps::Crossfade, None) => {
+                    if lookahead.is_none() {
+                        lookahead = frames[i + 1..].iter().position(Option::is_some).map(|n| {
+                            let j = i + 1 + n;
+                            let mut next = current.clone();
+                            // Safe unwrap (position of Some)
+                            renderer.render(frames[j].unwrap(), &mut next);
+                            (j, next)
+                        });
+                    }
+                    match &lookahead {
+                        Some((j, next)) => {
+                            let val = (i - keyframe) as f32 / (j - keyframe) as f32;
+                            crossfade(&current, next, val, &mut faded);
+                            &faded
+                        }
+                        None => &current,
+                    }
+                }
+                _ => &current,
+            };
+
+            // The overlay is drawn onto a copy so it never accumulates into later frames
+            let current = match (&graph, latest) {
+                (Some(graph), Some(latest)) => {
+                    overlaid.clone_from(current);
+                    graph.draw(&mut overlaid, latest);
+                    &overlaid
+                }
+                _ => current,
+            };
+
+            // Empty frames that follow are shown as part of this frame
+            let empty = match self.skip_empty {
+                true => frames[i + 1..].iter().take_while(|f| f.is_none()).count(),
+                false => 0,
+            };
+            let mut delay = (self.frame_delay as i64).saturating_mul(empty as i64 + 1);
+
+            // Held frames are repeated unless the output has a frame delay
+            let (first, last) = (shown_frames == 0, shown_frames + 1 == total);
+            shown_frames += 1;
+            let mut repeats = (0, 0);
+            match &dst {
+                Destination::Apng(..) => {
+                    if first {
+                        delay = delay.saturating_add(self.start_hold);
+                    }
+                    if last {
+                        delay = delay.saturating_add(self.end_hold);
+                    }
+                }
+                _ => {
+                    let frame_delay = u16::max(self.frame_delay, 1) as usize;
+                    let hold_frames = |hold: i64| (hold as usize).div_ceil(frame_delay);
+                    if first {
+                        repeats.0 = hold_frames(self.start_hold);
+                    }
+                    if last {
+                        repeats.1 = hold_frames(self.end_hold);
+                    }
+                }
             }
 
-            match &self.dst {
-                Some(path) => Self::frame_to_file(&current, &path, i)
-                    .map_err(|e| RuntimeError::from_err(e, &path, 0))?,
-                None => Self::frame_to_raw(&current, &mut stdout.lock())
-                    .map_err(|e| RuntimeError::from_err(e, "STDOUT", 0))?,
+            for repeat in 0..repeats.0 + 1 + repeats.1 {
+                let path = self.write_frame(&mut dst, current, written, delay)?;
+                if let (Some(path), Some(_)) = (path, &self.manifest) {
+                    // Only the original frame lists its actions
+                    let actions = match repeat == repeats.0 {
+                        true => *frame,
+                        false => None,
+                    };
+                    manifest.push(Self::manifest_entry(written, &path, &actions));
+                }
+                written += 1;
             }
         }
 
+        match dst {
+            Destination::Apng(path, writer) => writer
+                .finish()
+                .map_err(|e| RuntimeError::from_err(e, path, 0))?,
+            Destination::Pipe(path, mut pipe) => pipe
+                .flush()
+                .map_err(|e| RuntimeError::from_err(e, path, 0))?,
+            Destination::Stdout(mut stdout) => stdout
+                .flush()
+                .map_err(|e| RuntimeError::from_err(e, "STDOUT", 0))?,
+            Destination::Frames(_) => (),
+        }
+        if settings.verbose {
+            super::print_throughput("Rendered", written, "frames", start);
+        }
+
+        if let Some(path) = &self.density_out {
+            let counts = get_density(&pixels, &self.kind_heatmap, width, height);
+            Self::density_to_file(&counts, width, height, path)
+                .map_err(|e| RuntimeError::from_err(e, path, 0))?;
+        }
+
+        if let Some(path) = &self.manifest {
+            let file = File::create(path).map_err(|e| RuntimeError::from_err(e, path, 0))?;
+            let file = BufWriter::new(file);
+            serde_json::to_writer_pretty(file, &manifest)
+                .map_err(|e| RuntimeError::from_err(e, path, 0))?;
+        }
+
         Ok(())
     }
-}
 
-impl RenderData {
-    // TODO: Error handling
-    fn frame_to_file(frame: &RgbaImage, path: &str, i: usize) -> RuntimeResult<()> {
+    // Outputs of each canvas are suffixed with its number (e.g. "out.png" to "out_canvas1.png")
+    fn for_canvas(&self, n: usize) -> RenderData {
+        let canvas_path = |path: &String| super::canvas_path(path, n);
+        let mut data = self.clone();
+        data.dst = self.dst.as_ref().map(canvas_path);
+        data.manifest = self.manifest.as_ref().map(canvas_path);
+        data.write_bounds = self.write_bounds.as_ref().map(canvas_path);
+        data.dump_actions = self.dump_actions.as_ref().map(canvas_path);
+        data.density_out = self.density_out.as_ref().map(canvas_path);
+        data
+    }
+
+    // Applies the out of bounds policy to actions outside the canvas
+    fn handle_out_of_bounds(
+        &self,
+        pixels: &mut Vec<ActionRef>,
+        width: u32,
+        height: u32,
+    ) -> RuntimeResult<()> {
+        let is_outside = |a: &ActionRef| a.x >= width || a.y >= height;
+        match self.oob_policy {
+            OobPolicy::Skip => {
+                let total = pixels.len();
+                pixels.retain(|a| !is_outside(a));
+                if pixels.len() < total {
+                    eprintln!(
+                        "Skipped {} actions outside the {}x{} canvas",
+                        total - pixels.len(),
+                        width,
+                        height
+                    );
+                }
+            }
+            OobPolicy::Clamp => {
+                for action in pixels.iter_mut() {
+                    action.x = u32::min(action.x, width - 1);
+                    action.y = u32::min(action.y, height - 1);
+                }
+            }
+            OobPolicy::Error => {
+                if let Some(action) = pixels.iter().find(|a| is_outside(a)) {
+                    let (x, y) = self.crop.start();
+                    Err(RuntimeError::new(RuntimeErrorKind::OutOfBounds(
+                        (action.x + x, action.y + y),
+                        (width, height),
+                    )))?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Resolved settings of the render, printed when verbose
+    fn summary(&self, frames: usize, skipped: usize) -> String {
+        let style = match self.style {
+            RenderType::Normal => format!(
+                "{:?} (palette offset {}, survivors only {})",
+                self.style, self.palette_offset, self.survivors_only
+            ),
+            RenderType::Milliseconds | RenderType::Seconds | RenderType::Minutes => {
+                let period = self
+                    .placement_period
+                    .unwrap_or_else(|| self.style.default_period().num_milliseconds());
+                format!("{:?} (period {}ms)", self.style, period)
+            }
+            RenderType::Combined => format!(
+                "{:?} (periods {}ms, {}ms, {}ms)",
+                self.style,
+                self.combined_periods[0],
+                self.combined_periods[1],
+                self.combined_periods[2]
+            ),
+            RenderType::Heat => format!(
+                "{:?} (window {}ms, gradient {})",
+                self.style,
+                HEAT_WINDOW.num_milliseconds(),
+                self.heat_gradient
+            ),
+            RenderType::Owner => format!(
+                "{:?} (territory borders {}, {} user colors)",
+                self.style,
+                self.territory_borders,
+                self.user_colors.len()
+            ),
+            _ => format!("{:?}", self.style),
+        };
+        let step = match (self.step, self.step_type) {
+            (_, StepType::Daily) => String::from("daily"),
+            (_, StepType::Weekly) => String::from("weekly"),
+            (i64::MAX, _) => String::from("none"),
+            (step, StepType::Pixels) if self.pixel_ramp != 1.0 => {
+                format!("{} pixels (ramp x{})", step, self.pixel_ramp)
+            }
+            (step, StepType::Pixels) => format!("{} pixels", step),
+            (step, _) => format!("{}ms", step),
+        };
+        let output = match (&self.dst, self.apng, self.pipe) {
+            (Some(path), true, _) => format!("{} (APNG)", path),
+            (Some(path), _, true) => format!("{} (raw pipe)", path),
+            (Some(path), _, _) => format!("{} (frames)", path),
+            (None, _, _) => String::from("STDOUT (raw)"),
+        };
+        let (x, y) = self.crop.start();
+
+        format!(
+            "Style:   {}
+Step:    {}
+Canvas:  {}x{} at offset ({}, {})
+Palette: {} colors
+Output:  {}
+Frames:  {} ({} skipped), {}ms delay",
+            style,
+            step,
+            self.background.width(),
+            self.background.height(),
+            x,
+            y,
+            self.palette.len(),
+            output,
+            frames,
+            skipped,
+            self.frame_delay
+        )
+    }
+
+    // Returns the filepath of the frame when saved as an individual image
+    fn write_frame(
+        &self,
+        dst: &mut Destination,
+        frame: &RgbaImage,
+        i: usize,
+        delay: i64,
+    ) -> RuntimeResult<Option<String>> {
+        match dst {
+            Destination::Frames(path) => {
+                let frame_path =
+                    Self::frame_path(path, i).map_err(|e| RuntimeError::from_err(e, path, 0))?;
+                frame
+                    .save(&frame_path)
+                    .map_err(|e| RuntimeError::from_err(e, path, 0))?;
+                return Ok(Some(frame_path));
+            }
+            Destination::Apng(path, writer) => {
+                let (numerator, denominator) = apng_delay(delay);
+                writer
+                    .set_frame_delay(numerator, denominator)
+                    .and_then(|_| writer.write_image_data(frame.as_raw()))
+                    .map_err(|e| RuntimeError::from_err(e, path, 0))?
+            }
+            Destination::Pipe(path, pipe) => {
+                Self::frame_to_raw(frame, pipe, self.framed_raw, self.flush_per_frame)
+                    .map_err(|e| RuntimeError::from_err(e, path, 0))?
+            }
+            Destination::Stdout(stdout) => {
+                Self::frame_to_raw(frame, stdout, self.framed_raw, self.flush_per_frame)
+                    .map_err(|e| RuntimeError::from_err(e, "STDOUT", 0))?
+            }
+        }
+        Ok(None)
+    }
+
+    // Frames are suffixed with their index (e.g. "out_0.png")
+    fn frame_path(path: &str, i: usize) -> RuntimeResult<String> {
         let ext = Path::new(path)
             .extension()
             .and_then(OsStr::to_str)
@@ -352,15 +1420,178 @@ impl RenderData {
         let mut dst = path.to_owned();
         dst.truncate(dst.len() - ext.len() - 1);
 
-        frame.save(format!("{}_{}.{}", dst, i, ext))?;
+        Ok(format!("{}_{}.{}", dst, i, ext))
+    }
+
+    fn manifest_entry(i: usize, path: &str, frame: &Option<&[ActionRef]>) -> serde_json::Value {
+        let format = "%Y-%m-%dT%H:%M:%S%.3f";
+        let actions = frame.unwrap_or_default();
+        serde_json::json!({
+            "index": i,
+            "path": path,
+            "start": actions.first().map(|a| a.time.format(format).to_string()),
+            "end": actions.last().map(|a| a.time.format(format).to_string()),
+            "actions": actions.len(),
+        })
+    }
+
+    fn create_apng(
+        path: &str,
+        frame: &RgbaImage,
+        total: usize,
+        delay: u16,
+        plays: u32,
+    ) -> RuntimeResult<png::Writer<BufWriter<File>>> {
+        let total = u32::try_from(total).unwrap_or(u32::MAX);
+        if total == 0 {
+            Err(RuntimeError::new(RuntimeErrorKind::UnexpectedEof))?;
+        }
+
+        let file = BufWriter::new(File::create(path)?);
+        let mut encoder = png::Encoder::new(file, frame.width(), frame.height());
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.set_animated(total, plays)?;
+        encoder.set_frame_delay(delay, 1000)?;
+        Ok(encoder.write_header()?)
+    }
+
+    fn compare(&self, canvas: &[Option<usize>], reference: &RgbaImage) -> RuntimeResult<()> {
+        let mut diff = RgbaImage::new(reference.width(), reference.height());
+        let mut total = 0;
+        let mut matching = 0;
+
+        // Pixels without placements show the background so aren't compared
+        for ((pixel, index), out) in reference.pixels().zip(canvas).zip(diff.pixels_mut()) {
+            if pixel.0[3] == 0 || index.is_none() {
+                continue;
+            }
+
+            total += 1;
+            if *index == palette::nearest_index(&self.palette, pixel.0) {
+                matching += 1;
+                *out = Rgba::from([0, 255, 0, 255]);
+            } else {
+                *out = Rgba::from([255, 0, 0, 255]);
+            }
+        }
+
+        let coverage = match total {
+            0 => 100.0,
+            _ => matching as f64 / total as f64 * 100.0,
+        };
+        println!("Matching: {} of {} placed pixels ({:4.2}%)", matching, total, coverage);
+
+        let unknown = PaletteParser::from_image(reference, usize::MAX)
+            .into_iter()
+            .filter(|color| !self.palette.iter().any(|p| p[..3] == color[..3]))
+            .count();
+        if unknown > 0 {
+            println!("Reference has {} colors outside the palette (matched to the nearest)", unknown);
+        }
+
+        if let Some(path) = &self.dst {
+            diff.save(path)
+                .map_err(|e| RuntimeError::from_err(e, path, 0))?;
+        }
 
         Ok(())
     }
 
-    fn frame_to_raw<R: Write>(frame: &RgbaImage, out: &mut R) -> RuntimeResult<()> {
+    fn canvas_to_png(
+        canvas: &[Option<usize>],
+        palette: &[[u8; 4]],
+        width: u32,
+        height: u32,
+        path: &str,
+    ) -> RuntimeResult<()> {
+        // Final palette entry is reserved for empty pixels
+        let empty = match u8::try_from(palette.len()) {
+            Ok(empty) => empty,
+            Err(_) => Err(RuntimeError::new(RuntimeErrorKind::Unsupported))?,
+        };
+
+        let data = canvas
+            .iter()
+            .map(|index| match index {
+                Some(index) if *index < palette.len() => *index as u8,
+                _ => empty,
+            })
+            .collect::<Vec<u8>>();
+
+        let file = BufWriter::new(File::create(path)?);
+        let mut encoder = png::Encoder::new(file, width, height);
+        encoder.set_color(png::ColorType::Indexed);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.set_palette(
+            palette
+                .iter()
+                .chain(std::iter::once(&[0, 0, 0, 0]))
+                .flat_map(|rgba| [rgba[0], rgba[1], rgba[2]])
+                .collect::<Vec<u8>>(),
+        );
+        encoder.set_trns(
+            palette
+                .iter()
+                .chain(std::iter::once(&[0, 0, 0, 0]))
+                .map(|rgba| rgba[3])
+                .collect::<Vec<u8>>(),
+        );
+
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&data)?;
+        Ok(())
+    }
+
+    fn density_to_file(counts: &[u32], width: u32, height: u32, path: &str) -> RuntimeResult<()> {
+        let mut out = BufWriter::new(File::create(path)?);
+        match Path::new(path).extension().and_then(OsStr::to_str) {
+            Some("csv") => {
+                writeln!(out, "x,y,count")?;
+                for (i, count) in counts.iter().enumerate().filter(|(_, c)| **c > 0) {
+                    let i = i as u32;
+                    writeln!(out, "{},{},{}", i % width, i / width, count)?;
+                }
+            }
+            _ => {
+                out.write_all(b"PXLD")?;
+                out.write_all(&width.to_le_bytes())?;
+                out.write_all(&height.to_le_bytes())?;
+                for count in counts {
+                    out.write_all(&count.to_le_bytes())?;
+                }
+            }
+        }
+        out.flush()?;
+        Ok(())
+    }
+
+    // Magic "PXLR", width and height (u32 LE), then the pixel format "RGBA"
+    fn write_raw_header<R: Write>(frame: &RgbaImage, out: &mut R) -> RuntimeResult<()> {
+        out.write_all(b"PXLR")?;
+        out.write_all(&frame.width().to_le_bytes())?;
+        out.write_all(&frame.height().to_le_bytes())?;
+        out.write_all(b"RGBA")?;
+        Ok(())
+    }
+
+    // Framed frames are prefixed with their length in bytes (u32 LE)
+    fn frame_to_raw<R: Write>(
+        frame: &RgbaImage,
+        out: &mut R,
+        framed: bool,
+        flush: bool,
+    ) -> RuntimeResult<()> {
         let buf = &frame.as_raw()[..];
+        if framed {
+            let len = u32::try_from(buf.len())
+                .map_err(|_| RuntimeError::new(RuntimeErrorKind::Unsupported))?;
+            out.write_all(&len.to_le_bytes())?;
+        }
         out.write_all(buf)?;
-        out.flush()?;
+        if flush {
+            out.flush()?;
+        }
         Ok(())
     }
 
@@ -368,22 +1599,29 @@ impl RenderData {
         pixels: &'a [ActionRef],
         step: i64,
         step_type: StepType,
-    ) -> Vec<Option<&'a [ActionRef<'a>]>> {
+        pixel_ramp: f64,
+        max_frames: usize,
+    ) -> RuntimeResult<Vec<Option<&'a [ActionRef<'a>]>>> {
         let mut frames: Vec<Option<&[ActionRef]>> = vec![];
         let mut start = 0;
 
         frames.push(None);
         if step != 0 {
+            let bucket = |time: NaiveDateTime| step_bucket(time, step, step_type);
             match step_type {
-                StepType::Time => {
+                StepType::Time | StepType::Daily | StepType::Weekly => {
                     for (end, pair) in pixels.windows(2).enumerate() {
-                        let start_time = pair[0].time.timestamp_millis() / step;
-                        let end_time = pair[1].time.timestamp_millis() / step;
+                        let start_time = bucket(pair[0].time);
+                        let end_time = bucket(pair[1].time);
                         // TODO: Diff could be negative
                         let diff = end_time - start_time;
                         if diff > 0 {
+                            // Check before pushing empty frames, a tiny step could produce billions
+                            if frames.len() as i64 + diff >= max_frames as i64 {
+                                Err(RuntimeError::new(RuntimeErrorKind::FrameLimit(max_frames)))?;
+                            }
                             frames.push(Some(&pixels[start..=end]));
-                            start = end;
+                            start = end + 1;
                             for _ in 1..diff {
                                 frames.push(None);
                             }
@@ -391,12 +1629,17 @@ impl RenderData {
                     }
                 },
                 StepType::Pixels => {
-                    let step = usize::try_from(step).unwrap();
-                    for (end, _pair) in pixels.windows(2).enumerate() {
-                        if end - start >= step {
-                            frames.push(Some(&pixels[start..=end]));
-                            start = end;
+                    let mut chunks = RampedChunks::new(pixels, step as f64, pixel_ramp).peekable();
+                    while let Some(chunk) = chunks.next() {
+                        // The remainder is pushed below
+                        if chunks.peek().is_none() {
+                            break;
+                        }
+                        if frames.len() + 1 >= max_frames {
+                            Err(RuntimeError::new(RuntimeErrorKind::FrameLimit(max_frames)))?;
                         }
+                        frames.push(Some(chunk));
+                        start += chunk.len();
                     }
                 }
             }
@@ -406,20 +1649,112 @@ impl RenderData {
             frames.push(Some(&pixels));
         }
 
-        frames
+        Ok(frames)
+    }
+}
+
+// APNG delays are a u16 fraction of a second, so delays too long in milliseconds use whole seconds
+fn apng_delay(ms: i64) -> (u16, u16) {
+    match u16::try_from(ms) {
+        Ok(ms) => (ms, 1000),
+        Err(_) => (u16::try_from((ms + 500) / 1000).unwrap_or(u16::MAX), 1),
+    }
+}
+
+// Frame a time falls in, calendar steps count days from 0001-01-01, a Monday
+fn step_bucket(time: NaiveDateTime, step: i64, step_type: StepType) -> i64 {
+    match step_type {
+        StepType::Daily => time.date().num_days_from_ce() as i64,
+        StepType::Weekly => (time.date().num_days_from_ce() as i64 - 1).div_euclid(7),
+        _ => time.timestamp_millis() / step,
+    }
+}
+
+// Time in milliseconds at which a frame ends, the start of the following frame
+fn step_bucket_end(bucket: i64, step: i64, step_type: StepType) -> i64 {
+    let midnight = |days: i64| {
+        i32::try_from(days)
+            .ok()
+            .and_then(NaiveDate::from_num_days_from_ce_opt)
+            .and_then(|date| date.and_hms_opt(0, 0, 0))
+            .map_or(i64::MAX, |time| time.timestamp_millis())
+    };
+    match step_type {
+        StepType::Daily => midnight(bucket + 1),
+        StepType::Weekly => midnight((bucket + 1) * 7 + 1),
+        _ => (bucket + 1).saturating_mul(step),
+    }
+}
+
+// Splits a slice into chunks of geometrically scaling size
+// Chunk n (from 0) holds max(1, round(size * ratio^n)) items, a ratio of 1 gives fixed size chunks
+struct RampedChunks<'a, T> {
+    slice: &'a [T],
+    size: f64,
+    ratio: f64,
+}
+
+impl<'a, T> RampedChunks<'a, T> {
+    fn new(slice: &'a [T], size: f64, ratio: f64) -> Self {
+        Self { slice, size, ratio }
+    }
+}
+
+impl<'a, T> Iterator for RampedChunks<'a, T> {
+    type Item = &'a [T];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.slice.is_empty() {
+            return None;
+        }
+
+        // Float to int casts saturate, huge sizes take the remainder
+        let len = (self.size.round() as usize).clamp(1, self.slice.len());
+        let (chunk, rest) = self.slice.split_at(len);
+        self.slice = rest;
+        self.size *= self.ratio;
+        Some(chunk)
     }
 }
 
 struct NormalRender<'a> {
     background: &'a RgbaImage,
     palette: &'a [[u8; 4]],
+    palette_offset: i64,
+    unknown_color: Option<Rgba<u8>>,
+    survivors: Option<HashMap<(u32, u32), (NaiveDateTime, Index)>>,
+    history: PlacementHistory,
 }
 
 impl<'a> NormalRender<'a> {
-    fn new(background: &'a RgbaImage, palette: &'a [[u8; 4]]) -> Self {
+    fn new(
+        background: &'a RgbaImage,
+        palette: &'a [[u8; 4]],
+        palette_offset: i64,
+        unknown_color: Option<Rgba<u8>>,
+        survivors: Option<HashMap<(u32, u32), (NaiveDateTime, Index)>>,
+    ) -> Self {
         Self {
             background,
             palette,
+            palette_offset,
+            unknown_color,
+            survivors,
+            history: PlacementHistory::default(),
+        }
+    }
+
+    fn get_color(&self, index: usize) -> Option<&[u8; 4]> {
+        let index = usize::try_from(index as i64 + self.palette_offset).ok()?;
+        self.palette.get(index)
+    }
+
+    fn is_survivor(&self, action: &ActionRef) -> bool {
+        match &self.survivors {
+            Some(survivors) => {
+                survivors.get(&(action.x, action.y)) == Some(&(action.time, action.index))
+            }
+            None => true,
         }
     }
 }
@@ -427,41 +1762,247 @@ impl<'a> NormalRender<'a> {
 impl<'a> Renderable for NormalRender<'a> {
     fn render(&mut self, actions: &[ActionRef], frame: &mut RgbaImage) {
         for action in actions {
-            if let Some(pixel) = self.palette.get(action.index) {
-                frame.put_pixel(action.x, action.y, Rgba::from(*pixel));
-            } else {
-                frame.put_pixel(
-                    action.x,
-                    action.y,
-                    *self.background.get_pixel(action.x, action.y),
-                );
+            if !self.is_survivor(action) {
+                continue;
+            }
+
+            // Undos restore the previous placement and nukes clear to the background
+            // Rollbacks are skipped as the restored placement is unknown without replaying the rollback region
+            if matches!(action.kind, ActionKind::Rollback | ActionKind::RollbackUndo) {
+                continue;
+            }
+            let index = self.history.apply(action);
+
+            let pixel = match index {
+                Some(i) => match (self.get_color(i), self.unknown_color) {
+                    (Some(pixel), _) => Rgba::from(*pixel),
+                    (None, Some(unknown)) => unknown,
+                    (None, None) => *self.background.get_pixel(action.x, action.y),
+                },
+                None => *self.background.get_pixel(action.x, action.y),
+            };
+            frame.put_pixel(action.x, action.y, pixel);
+        }
+    }
+}
+
+// Per-pixel placement history, allowing undos to restore the previous placement
+// Rollbacks are ignored as the log doesn't describe the restored placement
+// Transparent placements are kept as None so they cover the placements beneath until undone
+#[derive(Default)]
+struct PlacementHistory {
+    stacks: HashMap<(u32, u32), Vec<Option<usize>>>,
+}
+
+impl PlacementHistory {
+    // Returns the visible index at the position after the action (None if empty)
+    fn apply(&mut self, action: &ActionRef) -> Option<usize> {
+        self.apply_with(action, action.index.color())
+    }
+
+    // As apply, but placements push value rather than their index (e.g. the placing user)
+    fn apply_with(&mut self, action: &ActionRef, value: Option<usize>) -> Option<usize> {
+        let stack = self.stacks.entry((action.x, action.y)).or_default();
+        match action.kind {
+            ActionKind::Place | ActionKind::Overwrite => stack.push(value),
+            ActionKind::Undo => {
+                stack.pop();
+            }
+            ActionKind::Nuke => stack.clear(),
+            ActionKind::Rollback | ActionKind::RollbackUndo => (),
+        }
+        stack.last().copied().flatten()
+    }
+
+    fn get(&self, x: u32, y: u32) -> Option<usize> {
+        self.stacks.get(&(x, y)).and_then(|stack| stack.last().copied().flatten())
+    }
+}
+
+// Colors pixels by the user of the visible placement, undos and nukes are handled as in NormalRender
+struct OwnerRender<'a> {
+    background: &'a RgbaImage,
+    user_colors: &'a HashMap<String, Rgba<u8>>,
+    users: HashMap<String, usize>,
+    colors: Vec<Rgba<u8>>,
+    owners: Vec<Option<usize>>,
+    history: PlacementHistory,
+    borders: bool,
+}
+
+impl<'a> OwnerRender<'a> {
+    fn new(
+        background: &'a RgbaImage,
+        user_colors: &'a HashMap<String, Rgba<u8>>,
+        borders: bool,
+    ) -> Self {
+        Self {
+            background,
+            user_colors,
+            users: HashMap::new(),
+            colors: vec![],
+            owners: vec![None; background.width() as usize * background.height() as usize],
+            history: PlacementHistory::default(),
+            borders,
+        }
+    }
+
+    fn get_user(&mut self, user: &str) -> usize {
+        if let Some(id) = self.users.get(user) {
+            return *id;
+        }
+
+        let id = self.colors.len();
+        let color = match self.user_colors.get(user) {
+            Some(color) => *color,
+            None => user_color(user),
+        };
+        self.colors.push(color);
+        self.users.insert(user.to_owned(), id);
+        id
+    }
+
+    fn get_pixel(&self, x: u32, y: u32) -> Rgba<u8> {
+        match self.owners[(x + y * self.background.width()) as usize] {
+            Some(owner) => self.colors[owner],
+            None => *self.background.get_pixel(x, y),
+        }
+    }
+
+    // Pixels with a 4-neighbour owned by another user
+    fn is_border(&self, x: u32, y: u32) -> bool {
+        let width = self.background.width();
+        let height = self.background.height();
+        let owner = match self.owners[(x + y * width) as usize] {
+            Some(owner) => owner,
+            None => return false,
+        };
+
+        [(-1, 0), (1, 0), (0, -1), (0, 1)].iter().any(|(dx, dy)| {
+            let (nx, ny) = (x as i64 + dx, y as i64 + dy);
+            if nx < 0 || ny < 0 || nx >= width as i64 || ny >= height as i64 {
+                return false;
+            }
+            let neighbour = self.owners[(nx + ny * width as i64) as usize];
+            matches!(neighbour, Some(other) if other != owner)
+        })
+    }
+}
+
+impl<'a> Renderable for OwnerRender<'a> {
+    fn render(&mut self, actions: &[ActionRef], frame: &mut RgbaImage) {
+        for action in actions {
+            if matches!(action.kind, ActionKind::Rollback | ActionKind::RollbackUndo) {
+                continue;
+            }
+            let user = self.get_user(action.user.get());
+            let owner = self.history.apply_with(action, Some(user));
+            self.owners[(action.x + action.y * self.background.width()) as usize] = owner;
+
+            if !self.borders {
+                frame.put_pixel(action.x, action.y, self.get_pixel(action.x, action.y));
             }
         }
+
+        // Borders depend on neighbours, so every pixel is redrawn
+        if self.borders {
+            for (x, y, pixel) in frame.enumerate_pixels_mut() {
+                *pixel = match self.is_border(x, y) {
+                    true => Rgba::from([0, 0, 0, 255]),
+                    false => self.get_pixel(x, y),
+                };
+            }
+        }
+    }
+}
+
+// Bright color derived from a FNV-1a hash of the user, stable across runs
+fn user_color(user: &str) -> Rgba<u8> {
+    let hash = user.bytes().fold(0xcbf29ce484222325u64, |hash, b| {
+        (hash ^ b as u64).wrapping_mul(0x100000001b3)
+    });
+    let [r, g, b, ..] = hash.to_le_bytes();
+    Rgba::from([64 + r % 192, 64 + g % 192, 64 + b % 192, 255])
+}
+
+// Palette indices of the canvas at the given time (row-major)
+fn reconstruct_canvas(
+    actions: &[ActionRef],
+    at: NaiveDateTime,
+    width: u32,
+    height: u32,
+) -> Vec<Option<usize>> {
+    let mut history = PlacementHistory::default();
+    for action in actions.iter().take_while(|a| a.time <= at) {
+        history.apply(action);
     }
+
+    (0..height)
+        .flat_map(|y| (0..width).map(move |x| (x, y)))
+        .map(|(x, y)| history.get(x, y))
+        .collect()
+}
+
+// Final placement of each pixel that isn't undone, nuked or rolled back by the end of the log
+fn get_survivors(actions: &[ActionRef]) -> HashMap<(u32, u32), (NaiveDateTime, Index)> {
+    let mut history: HashMap<(u32, u32), Vec<(NaiveDateTime, Index)>> = HashMap::new();
+    for action in actions {
+        let stack = history.entry((action.x, action.y)).or_default();
+        match action.kind {
+            ActionKind::Place | ActionKind::Overwrite => stack.push((action.time, action.index)),
+            ActionKind::Undo => {
+                stack.pop();
+            }
+            ActionKind::Rollback | ActionKind::RollbackUndo | ActionKind::Nuke => stack.clear(),
+        }
+    }
+
+    history
+        .into_iter()
+        .filter_map(|(pos, mut stack)| stack.pop().map(|survivor| (pos, survivor)))
+        .collect()
+}
+
+// Whether an action counts towards activity, no kinds counts every action
+fn counts_kind(kinds: &[ActionKind], kind: ActionKind) -> bool {
+    kinds.is_empty() || kinds.contains(&kind)
+}
+
+// Number of actions per pixel in row order, as accumulated by ActivityRender
+fn get_density(actions: &[ActionRef], kinds: &[ActionKind], width: u32, height: u32) -> Vec<u32> {
+    let mut counts = vec![0; width as usize * height as usize];
+    for action in actions.iter().filter(|a| counts_kind(kinds, a.kind)) {
+        counts[(action.x + action.y * width) as usize] += 1;
+    }
+    counts
 }
 
 // TODO: Remove map
-struct ActivityRender {
+struct ActivityRender<'a> {
     heat_map: Vec<i32>,
     max: i32,
     width: u32,
     height: u32,
+    kinds: &'a [ActionKind],
+    dither: bool,
 }
 
-impl ActivityRender {
-    fn new(width: u32, height: u32) -> Self {
+impl<'a> ActivityRender<'a> {
+    fn new(width: u32, height: u32, kinds: &'a [ActionKind], dither: bool) -> Self {
         ActivityRender {
             heat_map: vec![0; width as usize * height as usize],
             max: i32::MIN,
             width,
             height,
+            kinds,
+            dither,
         }
     }
 }
 
-impl Renderable for ActivityRender {
+impl<'a> Renderable for ActivityRender<'a> {
     fn render(&mut self, actions: &[ActionRef], frame: &mut RgbaImage) {
-        for action in actions {
+        for action in actions.iter().filter(|a| counts_kind(self.kinds, a.kind)) {
             let index = action.x + action.y * self.width;
             self.heat_map[index as usize] += 1;
 
@@ -474,6 +2015,7 @@ impl Renderable for ActivityRender {
             for x in 0..self.width {
                 let index = x + y * self.width;
                 let val = self.heat_map[index as usize] as f32 / self.max as f32;
+                let val = dither(val, x, y, self.dither);
 
                 let r = f32::min(f32::max(0.0, 1.5 - f32::abs(1.5 - 4.0 * (val - 0.5))), 1.0);
                 let g = f32::min(f32::max(0.0, 1.5 - f32::abs(1.5 - 4.0 * (val - 0.25))), 1.0);
@@ -489,32 +2031,122 @@ impl Renderable for ActivityRender {
     }
 }
 
-struct VirginRender {}
+struct VirginRender {
+    color: Rgba<u8>,
+}
+
+impl VirginRender {
+    fn new(color: Rgba<u8>) -> Self {
+        Self { color }
+    }
+}
 
 impl Renderable for VirginRender {
     fn render(&mut self, actions: &[ActionRef], frame: &mut RgbaImage) {
         for action in actions {
-            frame.put_pixel(action.x, action.y, Rgba::from([0, 0, 0, 255]));
+            frame.put_pixel(action.x, action.y, self.color);
+        }
+    }
+}
+
+// Cumulative actions over the span of the log, sampled once per graph column
+struct GraphOverlay {
+    times: Vec<NaiveDateTime>,
+    counts: Vec<usize>,
+    total: usize,
+    x: u32,
+    y: u32,
+    height: u32,
+}
+
+impl GraphOverlay {
+    fn new(actions: &[ActionRef], width: u32, height: u32) -> Self {
+        let graph_width = u32::max(width / 4, 1);
+        let graph_height = u32::max(height / 6, 1);
+
+        // Safe unwrap (actions.len > 0)
+        let start = actions.first().unwrap().time;
+        let span = actions.last().unwrap().time - start;
+        let mut times = Vec::with_capacity(graph_width as usize);
+        let mut counts = Vec::with_capacity(graph_width as usize);
+        for column in 1..=graph_width as i32 {
+            let time = start + span * column / graph_width as i32;
+            times.push(time);
+            counts.push(actions.partition_point(|a| a.time <= time));
+        }
+
+        Self {
+            times,
+            counts,
+            total: actions.len(),
+            x: 0,
+            y: height - graph_height,
+            height: graph_height,
+        }
+    }
+
+    // Columns after latest are left empty
+    fn draw(&self, frame: &mut RgbaImage, latest: NaiveDateTime) {
+        let shade = Rgba::from([0, 0, 0, 160]);
+        let bar = Rgba::from([255, 255, 255, 255]);
+        for (column, (time, count)) in self.times.iter().zip(&self.counts).enumerate() {
+            let filled = match *time <= latest {
+                true => (*count as u64 * self.height as u64 / self.total as u64) as u32,
+                false => 0,
+            };
+            for row in 0..self.height {
+                let x = self.x + column as u32;
+                let y = self.y + row;
+                let pixel = frame.get_pixel_mut(x, y);
+                if self.height - row <= filled {
+                    *pixel = bar;
+                } else {
+                    pixel.blend(&shade);
+                }
+            }
         }
     }
 }
 
+// Hottest to coolest, the final stop is reached at the end of the heat window
+const HEAT_GRADIENT: [[u8; 4]; 5] = [
+    [255, 255, 255, 255],
+    [255, 220, 60, 255],
+    [205, 40, 40, 255],
+    [40, 20, 120, 255],
+    [0, 0, 0, 255],
+];
+
 struct HeatRender {
     activity_map: Vec<i64>,
     width: u32,
     height: u32,
     step: i64,
-    i: i64,
+    step_type: StepType,
+    // End of the latest frame in milliseconds, heat is measured back from here
+    end: i64,
+    gradient: bool,
+    dither: bool,
 }
 
 impl HeatRender {
-    fn new(width: u32, height: u32, step: i64) -> Self {
+    fn new(
+        width: u32,
+        height: u32,
+        step: i64,
+        step_type: StepType,
+        gradient: bool,
+        dither: bool,
+    ) -> Self {
         HeatRender {
             activity_map: vec![0; width as usize * height as usize],
             width,
             height,
             step,
-            i: 1,
+            step_type,
+            end: 0,
+            gradient,
+            dither,
         }
     }
 }
@@ -525,19 +2157,21 @@ impl Renderable for HeatRender {
             let index = action.x + action.y * self.width;
             self.activity_map[index as usize] = action.time.timestamp_millis();
 
-            if action.time.timestamp_millis() > self.step * self.i {
-                self.i = action.time.timestamp_millis() as i64 / self.step + 1;
-            }
+            let bucket = step_bucket(action.time, self.step, self.step_type);
+            self.end = i64::max(self.end, step_bucket_end(bucket, self.step, self.step_type));
         }
         for y in 0..self.height {
             for x in 0..self.width {
                 let index = x + y * self.width;
                 let delta = self.activity_map[index as usize];
 
-                // If less than 15 minutes
                 // TODO: Customisable
-                let diff = (self.step * self.i - delta) as f32 / 900000.0;
-                if diff < 1.0 {
+                let window = HEAT_WINDOW.num_milliseconds() as f32;
+                let diff = self.end.saturating_sub(delta) as f32 / window;
+                let diff = dither(diff, x, y, self.dither);
+                if self.gradient {
+                    frame.put_pixel(x, y, gradient(&HEAT_GRADIENT, diff));
+                } else if diff < 1.0 {
                     let val = 1.0 - diff;
                     let r = (val * 205.0) as u8;
                     let g = (val * 92.0) as u8;
@@ -551,33 +2185,65 @@ impl Renderable for HeatRender {
     }
 }
 
-struct ActionRender {}
+struct ActionRender {
+    colors: HashMap<ActionKind, Rgba<u8>>,
+}
+
+impl ActionRender {
+    fn new(overrides: &[(ActionKind, Rgba<u8>)]) -> Self {
+        let mut colors = HashMap::from([
+            (ActionKind::Undo, Rgba::from([255, 0, 255, 255])),
+            (ActionKind::Place, Rgba::from([0, 0, 255, 255])),
+            (ActionKind::Overwrite, Rgba::from([0, 255, 255, 255])),
+            (ActionKind::Rollback, Rgba::from([0, 255, 0, 255])),
+            (ActionKind::RollbackUndo, Rgba::from([255, 255, 0, 255])),
+            (ActionKind::Nuke, Rgba::from([255, 0, 0, 255])),
+        ]);
+        colors.extend(overrides.iter().copied());
+        Self { colors }
+    }
+}
 
 impl Renderable for ActionRender {
     fn render(&mut self, actions: &[ActionRef], frame: &mut RgbaImage) {
         for action in actions {
-            let val = match action.kind {
-                ActionKind::Undo => Rgba::from([255, 0, 255, 255]),
-                ActionKind::Place => Rgba::from([0, 0, 255, 255]),
-                ActionKind::Overwrite => Rgba::from([0, 255, 255, 255]),
-                ActionKind::Rollback => Rgba::from([0, 255, 0, 255]),
-                ActionKind::RollbackUndo => Rgba::from([255, 255, 0, 255]),
-                ActionKind::Nuke => Rgba::from([255, 0, 0, 255]),
-            };
-            frame.put_pixel(action.x, action.y, val);
+            frame.put_pixel(action.x, action.y, self.colors[&action.kind]);
         }
     }
 }
 
+// Parses "kind=#RRGGBB" (e.g. "undo=#FF00FF")
+fn parse_action_color(s: &str) -> Result<(ActionKind, [u8; 4]), String> {
+    let (kind, color) = s
+        .split_once('=')
+        .ok_or_else(|| String::from("expected KIND=HEX"))?;
+    Ok((ActionKind::from_str(kind, true)?, util::parse_color(color)?))
+}
+
+// Parses "user=#RRGGBB" (e.g. "alice=#00FF00")
+fn parse_user_color(s: &str) -> Result<(String, [u8; 4]), String> {
+    let (user, color) = s
+        .rsplit_once('=')
+        .ok_or_else(|| String::from("expected USER=HEX"))?;
+    Ok((user.to_owned(), util::parse_color(color)?))
+}
+
 #[derive(Clone)]
 struct PlacementRender {
     step: i64,
     color: Rgba<u8>,
+    gamma_correct: bool,
+    dither: bool,
 }
 
 impl PlacementRender {
-    fn new(color: Rgba<u8>, step: i64) -> Self {
-        Self { step, color }
+    fn new(color: Rgba<u8>, step: i64, gamma_correct: bool, dither: bool) -> Self {
+        Self {
+            step,
+            color,
+            gamma_correct,
+            dither,
+        }
     }
 }
 
@@ -585,37 +2251,59 @@ impl Renderable for PlacementRender {
     fn render(&mut self, actions: &[ActionRef], frame: &mut RgbaImage) {
         for action in actions {
             let val = ((action.time.timestamp_millis() - 1) % self.step) as f32 / self.step as f32;
-            let color = color_lerp(self.color.channels(), val);
+            let val = dither(val, action.x, action.y, self.dither);
+            let color = color_lerp(self.color.channels(), val, self.gamma_correct);
             frame.put_pixel(action.x, action.y, color);
         }
     }
 }
 
-struct CombinedRender {}
+// Each channel maps placement time within its period (e.g. ms into second) to brightness
+struct CombinedRender {
+    periods: [i64; 3],
+}
+
+impl CombinedRender {
+    fn new(periods: [i64; 3]) -> Self {
+        Self { periods }
+    }
+}
 
 impl Renderable for CombinedRender {
     fn render(&mut self, actions: &[ActionRef], frame: &mut RgbaImage) {
         for action in actions {
-            let r = (((action.time.timestamp_millis() - 1) % 1000) as f32 / 1000.0 * 255.0) as u8;
-            let g = (((action.time.timestamp_millis() - 1) % 60000) as f32 / 60000.0 * 255.0) as u8;
-            let b =
-                (((action.time.timestamp_millis() - 1) % 3600000) as f32 / 3600000.0 * 255.0) as u8;
+            let time = action.time.timestamp_millis() - 1;
+            let [r, g, b] = self
+                .periods
+                .map(|period| ((time % period) as f32 / period as f32 * 255.0) as u8);
 
             frame.put_pixel(action.x, action.y, Rgba::from([r, g, b, 255]));
         }
     }
+
+    fn legend(&self) -> Option<String> {
+        let [r, g, b] = self.periods;
+        Some(format!(
+            "Red:   time within {}ms period\nGreen: time within {}ms period\nBlue:  time within {}ms period\n(Brighter channels indicate later placement within period)",
+            r, g, b
+        ))
+    }
 }
 
 struct AgeRender {
     min: f32,
     max: f32,
+    gamma_correct: bool,
+    dither: bool,
 }
 
 impl AgeRender {
-    fn new(min: i64, max: i64) -> Self {
+    fn new(min: i64, max: i64, gamma_correct: bool, dither: bool) -> Self {
         Self {
             min: min as f32,
             max: max as f32,
+            gamma_correct,
+            dither,
         }
     }
 }
@@ -629,24 +2317,96 @@ impl Renderable for AgeRender {
                 val = 1.0;
             }
 
-            let color = color_lerp(&[0, 0, 255, 255], val);
+            let val = dither(val, action.x, action.y, self.dither);
+            let color = color_lerp(&[0, 0, 255, 255], val, self.gamma_correct);
             frame.put_pixel(action.x, action.y, color);
         }
     }
 }
 
-fn color_lerp(color: &[u8], val: f32) -> Rgba<u8> {
-    if val < 0.5 {
-        let val = val * 2.0;
-        let r = (color[0] as f32 * val) as u8;
-        let g = (color[1] as f32 * val) as u8;
-        let b = (color[2] as f32 * val) as u8;
-        Rgba::from([r, g, b, 255])
+// 4x4 ordered dithering thresholds
+const BAYER: [[u8; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+
+// Gradient values are nudged by at most half this much either way
+const DITHER_SPREAD: f32 = 1.0 / 32.0;
+
+// Offsets a gradient value by its position in the Bayer pattern, results stay within 0..1
+fn dither(val: f32, x: u32, y: u32, enabled: bool) -> f32 {
+    if !enabled {
+        return val;
+    }
+    let threshold = (BAYER[(y % 4) as usize][(x % 4) as usize] as f32 + 0.5) / 16.0 - 0.5;
+    (val + threshold * DITHER_SPREAD).clamp(0.0, 1.0)
+}
+
+// Blends every pixel from one frame to another into out
+fn crossfade(from: &RgbaImage, to: &RgbaImage, val: f32, out: &mut RgbaImage) {
+    out.clone_from(from);
+    for (pixel, to) in out.pixels_mut().zip(to.pixels()) {
+        *pixel = lerp(&pixel.0, &to.0, val);
+    }
+}
+
+// Ramps from black through color to white, the alpha of color is kept throughout
+fn color_lerp(color: &[u8], val: f32, gamma_correct: bool) -> Rgba<u8> {
+    let black = [0, 0, 0, color[3]];
+    let white = [255, 255, 255, color[3]];
+    let (from, to, val) = match val < 0.5 {
+        true => (&black[..], color, val * 2.0),
+        false => (color, &white[..], (val - 0.5) * 2.0),
+    };
+    match gamma_correct {
+        true => lerp_linear(from, to, val),
+        false => lerp(from, to, val),
+    }
+}
+
+// Interpolates between evenly spaced stops, values outside 0..1 are clamped to the ends
+fn gradient(stops: &[[u8; 4]], val: f32) -> Rgba<u8> {
+    let scaled = val.clamp(0.0, 1.0) * (stops.len() - 1) as f32;
+    let i = usize::min(scaled as usize, stops.len() - 2);
+    lerp(&stops[i], &stops[i + 1], scaled - i as f32)
+}
+
+// Interpolates every channel, including alpha
+fn lerp(from: &[u8], to: &[u8], val: f32) -> Rgba<u8> {
+    let mut out = [0; 4];
+    for (i, channel) in out.iter_mut().enumerate() {
+        *channel = (from[i] as f32 + (to[i] as f32 - from[i] as f32) * val) as u8;
+    }
+    Rgba::from(out)
+}
+
+// As lerp, but color channels are interpolated in linear light (alpha is already linear)
+fn lerp_linear(from: &[u8], to: &[u8], val: f32) -> Rgba<u8> {
+    let mut out = [0; 4];
+    for (i, channel) in out.iter_mut().enumerate() {
+        *channel = match i {
+            3 => (from[i] as f32 + (to[i] as f32 - from[i] as f32) * val) as u8,
+            _ => {
+                let from = srgb_to_linear(from[i]);
+                let to = srgb_to_linear(to[i]);
+                linear_to_srgb(from + (to - from) * val)
+            }
+        };
+    }
+    Rgba::from(out)
+}
+
+fn srgb_to_linear(c: u8) -> f32 {
+    let c = c as f32 / 255.0;
+    if c <= 0.04045 {
+        c / 12.92
     } else {
-        let val = (val - 0.5) * 2.0;
-        let r = (color[0] as f32 + (255 - color[0]) as f32 * val) as u8;
-        let g = (color[1] as f32 + (255 - color[1]) as f32 * val) as u8;
-        let b = (color[2] as f32 + (255 - color[2]) as f32 * val) as u8;
-        Rgba::from([r, g, b, 255])
+        ((c + 0.055) / 1.055).powf(2.4)
     }
 }
+
+fn linear_to_srgb(c: f32) -> u8 {
+    let c = if c <= 0.0031308 {
+        c * 12.92
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    };
+    (c.clamp(0.0, 1.0) * 255.0).round() as u8
+}
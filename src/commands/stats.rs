@@ -1,18 +1,18 @@
 use std::{
-    collections::{HashMap, HashSet},
+    collections::HashMap,
     fs::OpenOptions,
     io::Write,
     path::PathBuf,
 };
 
 use clap::{ArgEnum, Args};
-use rayon::{prelude::ParallelIterator, str::ParallelString};
 use sha2::{Digest, Sha256};
 
 use crate::{
-    action::{ActionKind, ActionRef, Identifier, IdentifierRef},
+    action::{ActionKind, ActionRef, Identifier, IdentifierRef, Index},
     error::{ConfigError, ConfigResult, RuntimeError, RuntimeResult},
-    palette::PaletteParser,
+    palette::{self, PaletteParser},
+    util, OnReset,
 };
 
 use super::{Command, CommandInput};
@@ -27,7 +27,8 @@ To output only the final result, use the \"--screenshot\" arg or manually skip t
 pub struct StatisticInput {
     #[clap(short, long)]
     #[clap(value_name("PATH"))]
-    #[clap(help = "Filepath of input log file")]
+    #[clap(help = "Filepath or url of input log file")]
+    #[clap(long_help = "Filepath or url of input log file, urls are streamed line by line except by the all and personal modes or \"--on-reset split\", which download them in full (Requires the \"url\" feature)")]
     #[clap(display_order = 0)]
     src: String,
     #[clap(short, long)]
@@ -40,7 +41,7 @@ pub struct StatisticInput {
     #[clap(value_name("ENUM"))]
     #[clap(help = "Type of data to generate")]
     mode: Option<Mode>,
-    #[clap(short, long)]
+    #[clap(long)]
     #[clap(value_name("ENUM"))]
     #[clap(help = "How to present the data")]
     plot: bool,
@@ -51,10 +52,48 @@ pub struct StatisticInput {
     #[clap(display_order = 0)]
     palette: Option<String>,
     #[clap(long)]
+    #[clap(value_name("PATH"))]
+    #[clap(conflicts_with("palette"))]
+    #[clap(help = "Derive the palette from an image")]
+    #[clap(long_help = "Derive the palette from the unique colors of an image (e.g. a canvas snapshot)
+Colors are ordered by frequency so index 0 is the most common, capped at 256 colors and ignoring transparent pixels")]
+    palette_from_image: Option<String>,
+    #[clap(long)]
     #[clap(multiple_values(true))]
     #[clap(value_name("STRING"))]
     #[clap(help = "Only include entries that belong to this username/ hash")]
     user: Vec<String>,
+    #[clap(long, arg_enum)]
+    #[clap(multiple_values(true))]
+    #[clap(use_value_delimiter(true))]
+    #[clap(value_name("ENUM"))]
+    #[clap(help = "Only count entries with this action in color and canvas statistics")]
+    kind: Vec<ActionKind>,
+    #[clap(long)]
+    #[clap(value_name("INT"))]
+    #[clap(help = "Only list the top n entries of color and leaderboard statistics")]
+    top: Option<usize>,
+    #[clap(long, arg_enum)]
+    #[clap(value_name("ENUM"))]
+    #[clap(help = "How to format the data")]
+    #[clap(long_help = "How to format the data, overrides the destination extension [defaults to terminal for STDOUT or the destination extension]
+Reports are written as separate tables (or JSON arrays) one after another")]
+    format: Option<Format>,
+    #[clap(long)]
+    #[clap(value_name("PATH"))]
+    #[clap(help = "Filepath of color names")]
+    #[clap(long_help = "Filepath of color names, annotating color statistics [lines of \"name,index\" or \"name,#hex\" (e.g. the pxls palette .csv)]")]
+    color_names: Option<String>,
+    #[clap(long)]
+    #[clap(value_name("INT"))]
+    #[clap(help = "Estimate statistics from a random sample of n actions")]
+    #[clap(long_help = "Estimate statistics from a random sample of n actions (reservoir sampling), sampled actions keep their order within the log")]
+    sample: Option<usize>,
+    #[clap(long)]
+    #[clap(value_name("INT"))]
+    #[clap(requires("sample"))]
+    #[clap(help = "Seed of the random sample [defaults to 0]")]
+    seed: Option<u64>,
 }
 
 #[derive(Debug, Copy, Clone, ArgEnum)]
@@ -66,11 +105,18 @@ enum Mode {
     Leaderboard,
 }
 
+#[derive(Debug, Copy, Clone, ArgEnum)]
 enum Format {
     Terminal,
     CSV,
+    Json,
+    #[clap(name = "md")]
+    Markdown,
 }
 
+// Number of colors shown per user in personal statistics
+const PERSONAL_COLORS: usize = 5;
+
 pub struct StatisticData {
     src: String,
     dst: Option<String>,
@@ -79,21 +125,41 @@ pub struct StatisticData {
     format: Format,
     palette: Vec<[u8; 4]>,
     users: Vec<Identifier>,
+    kind: Vec<ActionKind>,
+    top: Option<usize>,
+    color_names: HashMap<usize, String>,
+    sample: Option<usize>,
+    seed: u64,
 }
 
 impl CommandInput<StatisticData> for StatisticInput {
     fn validate(&self) -> ConfigResult<StatisticData> {
-        let palette = match &self.palette {
-            Some(path) => PaletteParser::try_parse(&path)
+        let palette = match (&self.palette, &self.palette_from_image) {
+            (Some(path), _) => PaletteParser::try_parse(&path)
                 .map_err(|e| ConfigError::new("palette", &e.to_string()))?,
-            None => super::render::DEFAULT_PALETTE.to_vec(),
+            (None, Some(path)) => PaletteParser::try_parse_image(path)
+                .map_err(|e| ConfigError::new("palette_from_image", &e.to_string()))?,
+            (None, None) => super::render::DEFAULT_PALETTE.to_vec(),
+        };
+
+        if self.sample == Some(0) {
+            Err(ConfigError::new("sample", "sample must be positive"))?
+        }
+
+        let color_names = match &self.color_names {
+            Some(path) => palette::parse_names(path, &palette)
+                .map_err(|e| ConfigError::new("color_names", &e.to_string()))?,
+            None => HashMap::new(),
         };
 
-        let format = match &self.dst {
-            Some(p) => {
+        let format = match (self.format, &self.dst) {
+            (Some(format), _) => format,
+            (None, Some(p)) => {
                 let path = PathBuf::from(p);
                 match path.extension().map(|s| s.to_string_lossy()).as_deref() {
                     Some("csv") => Format::CSV,
+                    Some("json") => Format::Json,
+                    Some("md") => Format::Markdown,
                     Some(e) => Err(ConfigError::new(
                         "dst",
                         &format!("unsupported extension \'{}\'", e),
@@ -101,19 +167,14 @@ impl CommandInput<StatisticData> for StatisticInput {
                     None => Err(ConfigError::new("dst", "unsupported extension"))?,
                 }
             }
-            None => Format::Terminal,
+            (None, None) => Format::Terminal,
         };
 
+        // Same hash detection as the log and filter
         let users: Vec<Identifier> = self
             .user
             .iter()
-            .map(|u| {
-                if u.len() == 512 {
-                    Identifier::Hash(u.to_owned())
-                } else {
-                    Identifier::Username(u.to_owned())
-                }
-            })
+            .map(|u| Identifier::from(u.as_str()))
             .collect();
 
         // Fail if missing essential info
@@ -146,63 +207,177 @@ impl CommandInput<StatisticData> for StatisticInput {
             format,
             palette,
             users,
+            kind: self.kind.clone(),
+            top: self.top,
+            color_names,
+            sample: self.sample,
+            seed: self.seed.unwrap_or(0),
         })
     }
 }
 
 impl Command for StatisticData {
     fn run(&self, settings: &crate::Cli) -> RuntimeResult<()> {
-        let data = std::fs::read_to_string(&self.src)
-            .map_err(|e| RuntimeError::from_err(e, &self.src, 0))?;
-        let actions: Vec<ActionRef> = data
-            .as_parallel_string()
-            .par_lines()
-            .filter_map(|s| match ActionRef::try_from(s) {
-                Ok(a) => Some(a),
-                Err(_) => None, // TODO
-            })
-            .collect();
+        // Only personal statistics and split canvases require every action at once
+        let split = matches!(settings.on_reset, Some(OnReset::Split));
+        let mut streamed = None;
+        let data;
+        let sampled;
+        let actions = match (self.sample, self.mode) {
+            (Some(size), _) => {
+                sampled = self.sample_lines(settings, size)?;
+                sampled
+                    .iter()
+                    .map(|line| ActionRef::try_from(line.as_str()))
+                    .collect::<RuntimeResult<Vec<ActionRef>>>()?
+            }
+            (None, mode) if split || matches!(mode, Mode::All | Mode::Personal) => {
+                data = util::read_log(&self.src)
+                    .map_err(|e| RuntimeError::from_err(e, &self.src, 0))?;
+                super::parse_actions(&data, &self.src, settings, None)?
+            }
+            (None, _) => {
+                let mut tally = Tally::default();
+                let input = util::open_log(&self.src)
+                    .map_err(|e| RuntimeError::from_err(e, &self.src, 0))?;
+                super::for_each_action(input, &self.src, settings, |action| {
+                    tally.add(action, self.is_counted(action))
+                })?;
+                streamed = Some(tally);
+                vec![]
+            }
+        };
 
-        let mut out: Box<dyn Write> = match &self.dst {
-            Some(path) => Box::new(
-                OpenOptions::new()
-                    .create_new(settings.noclobber)
-                    .create(true)
-                    .write(true)
-                    .truncate(true)
-                    .open(path)?,
-            ),
-            None => Box::new(std::io::stdout().lock()),
+        let segments = match split {
+            true => super::split_resets(&actions),
+            false => vec![&actions[..]],
         };
+        let single = segments.len() == 1;
+        for (n, segment) in segments.into_iter().enumerate() {
+            let tally = match streamed.take() {
+                Some(tally) => tally,
+                None => {
+                    let mut tally = Tally::default();
+                    for action in segment {
+                        tally.add(action, self.is_counted(action));
+                    }
+                    tally
+                }
+            };
+
+            let dst = match single {
+                true => self.dst.clone(),
+                false => self.dst.as_deref().map(|path| super::canvas_path(path, n)),
+            };
+            let mut out: Box<dyn Write> = match &dst {
+                Some(path) => Box::new(
+                    OpenOptions::new()
+                        .create_new(settings.noclobber)
+                        .create(true)
+                        .write(true)
+                        .truncate(true)
+                        .open(path)?,
+                ),
+                None => Box::new(std::io::stdout().lock()),
+            };
+
+            // Canvases written to stdout are separated by a heading
+            if !single && dst.is_none() {
+                if n > 0 {
+                    writeln!(out)?;
+                }
+                writeln!(out, "Canvas {}:", n)?;
+            }
+            self.write_report(&mut out, segment, &tally)?;
+        }
 
+        Ok(())
+    }
+}
+
+impl StatisticData {
+    fn write_report(
+        &self,
+        out: &mut impl Write,
+        actions: &[ActionRef],
+        tally: &Tally,
+    ) -> RuntimeResult<()> {
         match self.mode {
             Mode::All => {
                 for user in &self.users {
-                    self.get_personal(&mut out, &actions, user.as_ref())?;
+                    self.get_personal(out, actions, user.as_ref())?;
                     writeln!(out)?;
                 }
-                self.get_color(&mut out, &actions)?;
+                self.get_color(out, tally)?;
                 writeln!(out)?;
-                self.get_canvas(&mut out, &actions)?;
+                self.get_canvas(out, tally)?;
                 writeln!(out)?;
-                self.get_leaderboard(&mut out, &actions)?;
+                self.get_leaderboard(out, tally)?;
             }
             Mode::Personal => {
                 for user in &self.users {
-                    self.get_personal(&mut out, &actions, user.as_ref())?;
+                    self.get_personal(out, actions, user.as_ref())?;
                     writeln!(out)?;
                 }
             }
-            Mode::Color => self.get_color(&mut out, &actions)?,
-            Mode::Canvas => self.get_canvas(&mut out, &actions)?,
-            Mode::Leaderboard => self.get_leaderboard(&mut out, &actions)?,
+            Mode::Color => self.get_color(out, tally)?,
+            Mode::Canvas => self.get_canvas(out, tally)?,
+            Mode::Leaderboard => self.get_leaderboard(out, tally)?,
         };
 
         Ok(())
     }
-}
 
-impl StatisticData {
+    // Reservoir sample of the log, returned in log order with the default column order
+    fn sample_lines(&self, settings: &crate::Cli, size: usize) -> RuntimeResult<Vec<String>> {
+        let mut rng = util::Rng::new(self.seed);
+        let mut reservoir: Vec<(usize, String)> = Vec::with_capacity(size);
+        let mut total = 0;
+
+        let input = util::open_log(&self.src)
+            .map_err(|e| RuntimeError::from_err(e, &self.src, 0))?;
+        super::for_each_action(input, &self.src, settings, |action| {
+            if total < size {
+                reservoir.push((total, action.to_string()));
+            } else {
+                let i = rng.below(total + 1);
+                if i < size {
+                    reservoir[i] = (total, action.to_string());
+                }
+            }
+            total += 1;
+        })?;
+
+        if total > reservoir.len() {
+            eprintln!(
+                "Results are estimates from {} sampled of {} actions",
+                reservoir.len(),
+                total
+            );
+        }
+
+        reservoir.sort_by_key(|(i, _)| *i);
+        Ok(reservoir.into_iter().map(|(_, line)| line).collect())
+    }
+
+    // Limits sorted entries to "--top", returning the number removed
+    fn truncate<T>(&self, entries: &mut Vec<T>) -> usize {
+        let len = entries.len();
+        entries.truncate(self.top.unwrap_or(len));
+        len - entries.len()
+    }
+
+    fn write_remaining(&self, out: &mut impl Write, remaining: usize) -> RuntimeResult<()> {
+        if remaining > 0 && matches!(self.format, Format::Terminal) {
+            writeln!(out, "... and {} more", remaining)?;
+        }
+        Ok(())
+    }
+
+    fn is_counted(&self, action: &ActionRef) -> bool {
+        self.kind.is_empty() || self.kind.contains(&action.kind)
+    }
+
     fn get_personal(
         &self,
         out: &mut impl Write,
@@ -211,14 +386,16 @@ impl StatisticData {
     ) -> RuntimeResult<()> {
         let mut total = 0;
         let mut placed = 0;
-        let mut survived = 0;
         let mut replaced = 0;
         let mut replaced_self = 0;
         let mut replaced_mod = 0;
         let mut restored_mod = 0;
         let mut undo = 0;
 
-        let mut pixel_cache = HashSet::new();
+        // Per-pixel stack of placements, true if placed by the user
+        // Undos pop the stack so a user's pixel survives again if the replacement is undone
+        let mut owners = HashMap::<(u32, u32), Vec<bool>>::new();
+        let mut color_map = HashMap::<Index, usize>::new();
 
         for action in actions {
             let is_equal = {
@@ -243,43 +420,50 @@ impl StatisticData {
                 }
             };
 
+            let stack = owners.entry((action.x, action.y)).or_default();
+            let owned = stack.last() == Some(&true);
             if is_equal {
                 total += 1;
-                match action.kind {
-                    ActionKind::Place => {
+                *color_map.entry(action.index).or_insert(0) += 1;
+            }
+
+            match action.kind {
+                ActionKind::Place => {
+                    if is_equal {
                         placed += 1;
-                        survived += 1;
-                        if !pixel_cache.insert((action.x, action.y)) {
+                        if owned {
                             replaced_self += 1;
                         }
+                    } else if owned {
+                        replaced += 1;
                     }
-                    ActionKind::Undo => {
-                        pixel_cache.remove(&(action.x, action.y));
-                        undo += 1;
+                    stack.push(is_equal);
+                }
+                ActionKind::Overwrite => {
+                    if !is_equal && owned {
+                        replaced_mod += 1;
                     }
-                    ActionKind::Overwrite => todo!(),
-                    ActionKind::Rollback => todo!(),
-                    ActionKind::RollbackUndo => todo!(),
-                    ActionKind::Nuke => todo!(),
+                    stack.push(is_equal);
                 }
-            } else {
-                match action.kind {
-                    ActionKind::Place => {
-                        if pixel_cache.remove(&(action.x, action.y)) {
-                            replaced += 1;
-                            survived -= 1;
-                        }
+                ActionKind::Undo => {
+                    if is_equal {
+                        undo += 1;
                     }
-                    ActionKind::Overwrite => {
-                        if pixel_cache.get(&(action.x, action.y)).is_some() {
-                            replaced_mod += 1;
-                            survived -= 1;
-                        }
+                    if stack.last() == Some(&is_equal) {
+                        stack.pop();
+                    }
+                }
+                ActionKind::Nuke => {
+                    if !is_equal && owned {
+                        replaced_mod += 1;
                     }
-                    _ => (),
+                    stack.clear();
                 }
+                // The restored placement is unknown without replaying the rollback region
+                ActionKind::Rollback | ActionKind::RollbackUndo => (),
             }
         }
+        let survived = owners.values().filter(|s| s.last() == Some(&true)).count();
 
         let total_coverage = 100.0;
         let placed_coverage = placed as f64 / total as f64 * 100.0;
@@ -290,82 +474,166 @@ impl StatisticData {
         let restored_mod_coverage = restored_mod as f64 / total as f64 * 100.0;
         let undo_coverage = undo as f64 / total as f64 * 100.0;
 
-        #[rustfmt::skip]
-        writeln!(out, "Total:            {:<6} ({:4.2}%)", total, total_coverage)?;
-        #[rustfmt::skip]
-        writeln!(out, "Placed:           {:<6} ({:4.2}%)", placed, placed_coverage)?;
-        #[rustfmt::skip]
-        writeln!(out, "Survived:         {:<6} ({:4.2}%)", survived, survived_coverage)?;
-        #[rustfmt::skip]
-        writeln!(out, "Replaced:         {:<6} ({:4.2}%)", replaced, replaced_coverage)?;
-        #[rustfmt::skip]
-        writeln!(out, "Replaced by self: {:<6} ({:4.2}%)", replaced_self, replaced_self_coverage)?;
-        #[rustfmt::skip]
-        writeln!(out, "Replaced by mods: {:<6} ({:4.2}%)", replaced_mod, replaced_mod_coverage)?;
-        #[rustfmt::skip]
-        writeln!(out, "Restored by mods: {:<6} ({:4.2}%)", restored_mod, restored_mod_coverage)?;
-        #[rustfmt::skip]
-        writeln!(out, "Undone:           {:<6} ({:4.2}%)", undo, undo_coverage)?;
-
-        Ok(())
-    }
+        if !matches!(self.format, Format::Terminal) {
+            let rows: Vec<Vec<String>> = [
+                ("Total", total, total_coverage),
+                ("Placed", placed, placed_coverage),
+                ("Survived", survived, survived_coverage),
+                ("Replaced", replaced, replaced_coverage),
+                ("Replaced by self", replaced_self, replaced_self_coverage),
+                ("Replaced by mods", replaced_mod, replaced_mod_coverage),
+                ("Restored by mods", restored_mod, restored_mod_coverage),
+                ("Undone", undo, undo_coverage),
+            ]
+            .iter()
+            .map(|(name, count, coverage)| {
+                vec![name.to_string(), count.to_string(), format!("{:4.2}", coverage)]
+            })
+            .collect();
+            self.write_table(out, &["Statistic", "Count", "Coverage"], &rows)?;
+        } else {
+            #[rustfmt::skip]
+            writeln!(out, "Total:            {:<6} ({:4.2}%)", total, total_coverage)?;
+            #[rustfmt::skip]
+            writeln!(out, "Placed:           {:<6} ({:4.2}%)", placed, placed_coverage)?;
+            #[rustfmt::skip]
+            writeln!(out, "Survived:         {:<6} ({:4.2}%)", survived, survived_coverage)?;
+            #[rustfmt::skip]
+            writeln!(out, "Replaced:         {:<6} ({:4.2}%)", replaced, replaced_coverage)?;
+            #[rustfmt::skip]
+            writeln!(out, "Replaced by self: {:<6} ({:4.2}%)", replaced_self, replaced_self_coverage)?;
+            #[rustfmt::skip]
+            writeln!(out, "Replaced by mods: {:<6} ({:4.2}%)", replaced_mod, replaced_mod_coverage)?;
+            #[rustfmt::skip]
+            writeln!(out, "Restored by mods: {:<6} ({:4.2}%)", restored_mod, restored_mod_coverage)?;
+            #[rustfmt::skip]
+            writeln!(out, "Undone:           {:<6} ({:4.2}%)", undo, undo_coverage)?;
+        }
 
-    fn get_color(&self, out: &mut impl Write, actions: &[ActionRef]) -> RuntimeResult<()> {
-        let mut used_colors = 0;
-        let mut color_map = HashMap::<usize, usize>::new();
+        let mut colors: Vec<(usize, Index)> = color_map.into_iter().map(|v| (v.1, v.0)).collect();
+        colors.sort_by(|a, b| b.cmp(a));
 
-        for action in actions {
-            match color_map.get_mut(&action.index) {
-                Some(i) => *i += 1,
-                None => {
-                    color_map.insert(action.index, 1);
-                    used_colors += 1;
-                }
-            };
+        colors.truncate(PERSONAL_COLORS);
+        match self.format {
+            Format::Terminal => writeln!(out, "Top colors:")?,
+            _ => writeln!(out)?,
         }
+        self.write_colors(out, &colors)?;
+
+        Ok(())
+    }
 
-        let mut colors: Vec<(usize, usize)> = color_map.into_iter().map(|v| (v.1, v.0)).collect();
+    fn get_color(&self, out: &mut impl Write, tally: &Tally) -> RuntimeResult<()> {
+        let used_colors = tally.colors.len();
+        let mut colors: Vec<(usize, Index)> = tally.colors.iter().map(|v| (*v.1, *v.0)).collect();
         colors.sort_by(|a, b| b.cmp(a));
 
-        writeln!(out, "Total:  {}", used_colors)?;
-        for (amount, index) in colors {
-            let rgba = match self.palette.get(index) {
-                Some(p) => p,
-                None => &[0, 0, 0, 0],
-            };
-            writeln!(
-                out,
-                "Amount: {:<8} #{:0<2X}{:0<2X}{:0<2X}{:0<2X}  {}",
-                amount, rgba[0], rgba[1], rgba[2], rgba[3], index
-            )?;
+        if matches!(self.format, Format::Terminal) {
+            writeln!(out, "Total:  {}", used_colors)?;
         }
+        let remaining = self.truncate(&mut colors);
+        self.write_colors(out, &colors)?;
+        self.write_remaining(out, remaining)?;
 
         Ok(())
     }
 
-    fn get_canvas(&self, out: &mut impl Write, actions: &[ActionRef]) -> RuntimeResult<()> {
-        let mut total_actions = 0;
+    fn write_colors(&self, out: &mut impl Write, colors: &[(usize, Index)]) -> RuntimeResult<()> {
+        let rows: Vec<Vec<String>> = colors
+            .iter()
+            .map(|&(amount, index)| {
+                let rgba = match index.color().and_then(|i| self.palette.get(i)) {
+                    Some(p) => p,
+                    None => &[0, 0, 0, 0],
+                };
+                let hex = format!("#{:02X}{:02X}{:02X}{:02X}", rgba[0], rgba[1], rgba[2], rgba[3]);
+                let mut row = vec![amount.to_string(), hex, index.to_string()];
+                if !self.color_names.is_empty() {
+                    let name = match index {
+                        Index::Color(i) => self.color_names.get(&i).cloned().unwrap_or_default(),
+                        Index::Transparent => "transparent".to_owned(),
+                    };
+                    row.push(name);
+                }
+                row
+            })
+            .collect();
 
-        let mut total_place = 0;
-        let mut total_undo = 0;
-        let mut total_overwrite = 0;
-        let mut total_rollback = 0;
-        let mut total_rollback_undo = 0;
-        let mut total_nuke = 0;
+        match self.format {
+            Format::Terminal => {
+                for row in rows {
+                    match row.get(3) {
+                        Some(name) => writeln!(
+                            out,
+                            "Amount: {:<8} {}  {:<3} {}",
+                            row[0], row[1], row[2], name
+                        )?,
+                        None => writeln!(out, "Amount: {:<8} {}  {}", row[0], row[1], row[2])?,
+                    }
+                }
+            }
+            _ if self.color_names.is_empty() => {
+                self.write_table(out, &["Amount", "Color", "Index"], &rows)?
+            }
+            _ => self.write_table(out, &["Amount", "Color", "Index", "Name"], &rows)?,
+        }
 
-        for action in actions {
-            total_actions += 1;
+        Ok(())
+    }
 
-            match action.kind {
-                crate::action::ActionKind::Place => total_place += 1,
-                crate::action::ActionKind::Undo => total_undo += 1,
-                crate::action::ActionKind::Overwrite => total_overwrite += 1,
-                crate::action::ActionKind::Rollback => total_rollback += 1,
-                crate::action::ActionKind::RollbackUndo => total_rollback_undo += 1,
-                crate::action::ActionKind::Nuke => total_nuke += 1,
+    // Writes rows as CSV, JSON or a Markdown table, terminal output is formatted by each report
+    fn write_table(
+        &self,
+        out: &mut impl Write,
+        header: &[&str],
+        rows: &[Vec<String>],
+    ) -> RuntimeResult<()> {
+        match self.format {
+            Format::Terminal => (),
+            Format::CSV => {
+                writeln!(out, "{}", header.join(","))?;
+                for row in rows {
+                    writeln!(out, "{}", row.join(","))?;
+                }
+            }
+            Format::Json => {
+                let rows: Vec<serde_json::Map<String, serde_json::Value>> = rows
+                    .iter()
+                    .map(|row| {
+                        header
+                            .iter()
+                            .map(|h| h.to_string())
+                            .zip(row.iter().map(|v| match v.parse::<serde_json::Number>() {
+                                Ok(n) => serde_json::Value::Number(n),
+                                Err(_) => serde_json::Value::from(v.as_str()),
+                            }))
+                            .collect()
+                    })
+                    .collect();
+                serde_json::to_writer_pretty(&mut *out, &rows)?;
+                writeln!(out)?;
+            }
+            Format::Markdown => {
+                writeln!(out, "| {} |", header.join(" | "))?;
+                writeln!(out, "|{}", " --- |".repeat(header.len()))?;
+                for row in rows {
+                    writeln!(out, "| {} |", row.join(" | "))?;
+                }
             }
         }
 
+        Ok(())
+    }
+
+    fn get_canvas(&self, out: &mut impl Write, tally: &Tally) -> RuntimeResult<()> {
+        let total_actions = tally.total;
+        let total_place = tally.get_kind(ActionKind::Place);
+        let total_undo = tally.get_kind(ActionKind::Undo);
+        let total_overwrite = tally.get_kind(ActionKind::Overwrite);
+        let total_rollback = tally.get_kind(ActionKind::Rollback);
+        let total_rollback_undo = tally.get_kind(ActionKind::RollbackUndo);
+        let total_nuke = tally.get_kind(ActionKind::Nuke);
+
         let coverage_place = total_place as f64 / total_actions as f64 * 100.0;
         let coverage_undo = total_undo as f64 / total_actions as f64 * 100.0;
         let coverage_overwrite = total_overwrite as f64 / total_actions as f64 * 100.0;
@@ -373,6 +641,24 @@ impl StatisticData {
         let coverage_rollback_undo = total_rollback_undo as f64 / total_actions as f64 * 100.0;
         let coverage_nuke = total_nuke as f64 / total_actions as f64 * 100.0;
 
+        if !matches!(self.format, Format::Terminal) {
+            let rows: Vec<Vec<String>> = [
+                ("Total", total_actions, 100.0),
+                ("Placed", total_place, coverage_place),
+                ("Undos", total_undo, coverage_undo),
+                ("Overwritten", total_overwrite, coverage_overwrite),
+                ("Rollback", total_rollback, coverage_rollback),
+                ("Rollback undos", total_rollback_undo, coverage_rollback_undo),
+                ("Nuked", total_nuke, coverage_nuke),
+            ]
+            .iter()
+            .map(|(name, count, coverage)| {
+                vec![name.to_string(), count.to_string(), format!("{:4.2}", coverage)]
+            })
+            .collect();
+            return self.write_table(out, &["Action", "Count", "Coverage"], &rows);
+        }
+
         writeln!(out, "Total actions:        {:<8}", total_actions)?;
         #[rustfmt::skip]
         writeln!(out, "Total placed:         {:<8} ({:4.2}%)", total_place, coverage_place)?;
@@ -390,27 +676,62 @@ impl StatisticData {
         Ok(())
     }
 
-    fn get_leaderboard(&self, out: &mut impl Write, actions: &[ActionRef]) -> RuntimeResult<()> {
-        let mut users = HashMap::new();
-        for action in actions {
-            if let IdentifierRef::Username(user) = action.user {
-                match users.get_mut(user) {
-                    Some(i) => *i += 1,
-                    None => {
-                        users.insert(user, 1);
-                    }
-                };
+    fn get_leaderboard(&self, out: &mut impl Write, tally: &Tally) -> RuntimeResult<()> {
+        let mut pixel_counts: Vec<(&str, usize)> =
+            tally.users.iter().map(|(user, count)| (user.as_str(), *count)).collect();
+        pixel_counts.sort_by(|&a, &b| b.1.cmp(&a.1));
+
+        let total = pixel_counts.len();
+        let remaining = self.truncate(&mut pixel_counts);
+        let rows: Vec<Vec<String>> = pixel_counts
+            .into_iter()
+            .enumerate()
+            .map(|(i, (user, count))| vec![i.to_string(), count.to_string(), user.to_string()])
+            .collect();
+
+        match self.format {
+            Format::Terminal => {
+                writeln!(out, "Total users: {}", total)?;
+                for row in rows {
+                    writeln!(out, "{:>4}: {:<8} {}", row[0], row[1], row[2])?;
+                }
             }
+            _ => self.write_table(out, &["Rank", "Count", "User"], &rows)?,
         }
+        self.write_remaining(out, remaining)?;
 
-        let mut pixel_counts: Vec<(&str, usize)> = users.into_iter().collect();
-        pixel_counts.sort_by(|&a, &b| b.1.cmp(&a.1));
+        Ok(())
+    }
+}
 
-        writeln!(out, "Total users: {}", pixel_counts.len())?;
-        for (i, (user, count)) in pixel_counts.into_iter().enumerate() {
-            writeln!(out, "{:>4}: {:<8} {}", i, count, user)?;
+// Running totals of the color, canvas and leaderboard statistics
+#[derive(Default)]
+struct Tally {
+    total: usize,
+    colors: HashMap<Index, usize>,
+    kinds: HashMap<ActionKind, usize>,
+    users: HashMap<String, usize>,
+}
+
+impl Tally {
+    // Uncounted actions only contribute to the leaderboard
+    fn add(&mut self, action: &ActionRef, counted: bool) {
+        if counted {
+            self.total += 1;
+            *self.colors.entry(action.index).or_insert(0) += 1;
+            *self.kinds.entry(action.kind).or_insert(0) += 1;
         }
+        if let IdentifierRef::Username(user) = action.user {
+            match self.users.get_mut(user) {
+                Some(i) => *i += 1,
+                None => {
+                    self.users.insert(user.to_owned(), 1);
+                }
+            };
+        }
+    }
 
-        Ok(())
+    fn get_kind(&self, kind: ActionKind) -> usize {
+        self.kinds.get(&kind).copied().unwrap_or(0)
     }
 }
@@ -59,7 +59,18 @@ pub enum RuntimeErrorKind {
     BadToken(String),
     UnexpectedEof,
     Unsupported,
+    UnsupportedExtension(&'static [&'static str]),
     InvalidFile,
+    FrameLimit(usize),
+    SkipLimit(usize),
+    ErrorLimit(usize),
+    NoActions,
+    OutOfBounds((u32, u32), (u32, u32)),
+    OutOfOrder,
+    #[cfg(not(feature = "url"))]
+    UrlUnsupported,
+    #[cfg(feature = "url")]
+    Network(String),
 }
 
 impl error::Error for RuntimeError {}
@@ -103,7 +114,18 @@ impl Terminate for RuntimeError {
             RuntimeErrorKind::UnexpectedEof => exitcode::DATAERR,
             RuntimeErrorKind::BadToken(_) => exitcode::DATAERR,
             RuntimeErrorKind::Unsupported => exitcode::DATAERR,
+            RuntimeErrorKind::UnsupportedExtension(_) => exitcode::USAGE,
             RuntimeErrorKind::InvalidFile => exitcode::DATAERR,
+            RuntimeErrorKind::FrameLimit(_) => exitcode::USAGE,
+            RuntimeErrorKind::SkipLimit(_) => exitcode::USAGE,
+            RuntimeErrorKind::ErrorLimit(_) => exitcode::DATAERR,
+            RuntimeErrorKind::NoActions => exitcode::NOINPUT,
+            RuntimeErrorKind::OutOfBounds(..) => exitcode::DATAERR,
+            RuntimeErrorKind::OutOfOrder => exitcode::DATAERR,
+            #[cfg(not(feature = "url"))]
+            RuntimeErrorKind::UrlUnsupported => exitcode::USAGE,
+            #[cfg(feature = "url")]
+            RuntimeErrorKind::Network(_) => exitcode::UNAVAILABLE,
         }
     }
 }
@@ -117,12 +139,30 @@ impl std::fmt::Display for RuntimeError {
                 self.kind.to_string(),
                 self.file.display(),
             ),
-            RuntimeErrorKind::Io(_) => write!(
+            RuntimeErrorKind::FrameLimit(_)
+            | RuntimeErrorKind::SkipLimit(_)
+            | RuntimeErrorKind::OutOfBounds(..) => {
+                write!(f, "{}", self.kind)
+            }
+            RuntimeErrorKind::NoActions => {
+                write!(f, "{} from {}", self.kind, self.file.display())
+            }
+            RuntimeErrorKind::Io(_)
+            | RuntimeErrorKind::ErrorLimit(_)
+            | RuntimeErrorKind::UnsupportedExtension(_) => write!(
                 f,
                 "{} while reading {}",
                 self.kind.to_string(),
                 self.file.display(),
             ),
+            #[cfg(not(feature = "url"))]
+            RuntimeErrorKind::UrlUnsupported => {
+                write!(f, "{} while reading {}", self.kind, self.file.display())
+            }
+            #[cfg(feature = "url")]
+            RuntimeErrorKind::Network(_) => {
+                write!(f, "{} while reading {}", self.kind, self.file.display())
+            }
             _ => write!(
                 f,
                 "{} while reading {} at line {}",
@@ -141,7 +181,41 @@ impl std::fmt::Display for RuntimeErrorKind {
             RuntimeErrorKind::BadToken(t) => write!(f, "Token \'{}\' is invalid", t),
             RuntimeErrorKind::UnexpectedEof => write!(f, "Unexpected EOF"),
             RuntimeErrorKind::Unsupported => write!(f, "Unsupported file"),
+            RuntimeErrorKind::UnsupportedExtension(supported) => write!(
+                f,
+                "Unsupported file extension (expected one of: {})",
+                supported.join(", ")
+            ),
             RuntimeErrorKind::InvalidFile => write!(f, "Invalid log"),
+            RuntimeErrorKind::NoActions => write!(f, "No actions read"),
+            RuntimeErrorKind::OutOfBounds((x, y), (width, height)) => write!(
+                f,
+                "Position ({}, {}) is outside the {}x{} canvas, see \'--oob-policy\'",
+                x, y, width, height
+            ),
+            RuntimeErrorKind::OutOfOrder => write!(
+                f,
+                "Time jumps backwards by over an hour (canvas reset?), see \'--on-reset\'"
+            ),
+            #[cfg(not(feature = "url"))]
+            RuntimeErrorKind::UrlUnsupported => {
+                write!(f, "Reading a url requires building with \"--features url\"")
+            }
+            #[cfg(feature = "url")]
+            RuntimeErrorKind::Network(e) => write!(f, "Network error ({})", e),
+            RuntimeErrorKind::ErrorLimit(max) => {
+                write!(f, "Exceeded the limit of {} malformed lines", max)
+            }
+            RuntimeErrorKind::SkipLimit(frames) => write!(
+                f,
+                "Skipping every frame, the render only has {} frames (including the background)",
+                frames
+            ),
+            RuntimeErrorKind::FrameLimit(max) => write!(
+                f,
+                "Render exceeds the limit of {} frames, consider a larger step or raise \'--max-frames\'",
+                max
+            ),
         }
     }
 }
@@ -176,6 +250,26 @@ impl From<std::num::ParseIntError> for RuntimeError {
     }
 }
 
+#[cfg(feature = "url")]
+impl From<ureq::Error> for RuntimeError {
+    fn from(e: ureq::Error) -> Self {
+        RuntimeError::new(match e {
+            ureq::Error::Status(404, _) => RuntimeErrorKind::Io(io::ErrorKind::NotFound),
+            ureq::Error::Status(code, _) => RuntimeErrorKind::BadToken(format!("HTTP {}", code)),
+            ureq::Error::Transport(e) => RuntimeErrorKind::Network(e.to_string()),
+        })
+    }
+}
+
+impl From<png::EncodingError> for RuntimeError {
+    fn from(e: png::EncodingError) -> Self {
+        RuntimeError::new(match e {
+            png::EncodingError::IoError(e) => RuntimeErrorKind::Io(e.kind()),
+            _ => RuntimeErrorKind::Unsupported,
+        })
+    }
+}
+
 impl From<image::ImageError> for RuntimeError {
     fn from(e: image::ImageError) -> Self {
         RuntimeError::new(match e {
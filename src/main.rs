@@ -4,14 +4,17 @@ mod error;
 mod palette;
 mod util;
 
+use action::{Column, DEFAULT_COLUMNS};
 use commands::filter::FilterInput;
 use commands::render::RenderInput;
 use commands::stats::StatisticInput;
 use commands::{Command, CommandInput};
 
-use clap::{Parser, Subcommand};
+use std::time::Instant;
 
-use crate::error::Terminate;
+use clap::{ArgEnum, Parser, Subcommand};
+
+use crate::error::{ConfigError, Terminate};
 
 #[derive(Parser)]
 #[clap(arg_required_else_help(true))]
@@ -27,29 +30,75 @@ pub struct Cli {
     #[clap(short, long)]
     #[clap(help = "Prevent files from being overwritten")]
     pub noclobber: bool,
-    // #[clap(short, long)]
-    // #[clap(help = "Forcibly exit rather than ignoring errors")]
-    // pub strict: bool,
+    #[clap(long)]
+    #[clap(help = "Forcibly exit rather than ignoring errors")]
+    pub strict: bool,
+    #[clap(long)]
+    #[clap(value_name("INT"))]
+    #[clap(conflicts_with("strict"))]
+    #[clap(help = "Exit if more than this many malformed lines are skipped")]
+    pub max_errors: Option<usize>,
     #[clap(long)]
     #[clap(value_name("INT"))]
     #[clap(help = "Number of threads utilised [Defaults to all available threads]")]
+    #[clap(long_help = "Number of threads utilised [Defaults to all available threads]
+A value of 0 uses all available threads and values above the available threads are lowered to match
+A value of 1 runs everything on a single thread, useful for reproducible debugging")]
     pub threads: Option<usize>,
+    #[clap(long, arg_enum)]
+    #[clap(use_value_delimiter(true))]
+    #[clap(value_name("ENUM"))]
+    #[clap(help = "Order of fields within log lines [Defaults to time,user,x,y,index,kind]")]
+    #[clap(long_help = "Comma separated order of fields within log lines [Defaults to time,user,x,y,index,kind]
+Useful for logs from pxls forks with a different layout, every field must be listed once")]
+    pub input_columns: Vec<Column>,
+    #[clap(long, arg_enum)]
+    #[clap(value_name("ENUM"))]
+    #[clap(help = "Handling of canvas resets within a log [Defaults to error]")]
+    #[clap(long_help = "Handling of canvas resets within a log (time jumping backwards by over an hour, e.g. concatenated canvases) [Defaults to error]
+split renders, filters or counts each canvas independently (outputs are suffixed with \"_canvasN\" before the extension)
+error aborts with the line of the reset and ignore processes every canvas together")]
+    pub on_reset: Option<OnReset>,
     #[clap(subcommand)]
     pub input: Input,
 }
 
+#[derive(Debug, Default, Copy, Clone, ArgEnum)]
+pub enum OnReset {
+    Split,
+    #[default]
+    Error,
+    Ignore,
+}
+
 #[derive(Subcommand)]
 pub enum Input {
     Filter(FilterInput),
-    Render(RenderInput),
+    Render(Box<RenderInput>),
     Stats(StatisticInput),
 }
 
 fn main() {
-    let cli = Cli::parse();
+    let mut cli = Cli::parse();
+    if cli.input_columns.is_empty() {
+        cli.input_columns = DEFAULT_COLUMNS.to_vec();
+    } else if cli.input_columns.len() != DEFAULT_COLUMNS.len()
+        || !DEFAULT_COLUMNS.iter().all(|c| cli.input_columns.contains(c))
+    {
+        ConfigError::new("input_columns", "every field must be listed once").terminate();
+    }
+
+    let available = num_cpus::get();
     let num_threads = match cli.threads {
+        Some(0) | None => available,
+        Some(threads) if threads > available => {
+            eprintln!(
+                "Warning: {} threads requested but only {} available, using {}",
+                threads, available, available
+            );
+            available
+        }
         Some(threads) => threads,
-        None => num_cpus::get(),
     };
 
     rayon::ThreadPoolBuilder::new()
@@ -58,17 +107,27 @@ fn main() {
         .unwrap();
 
     if cli.verbose {
-        eprintln!("Running with {} threads", num_threads);
+        match num_threads {
+            1 => eprintln!("Running single threaded"),
+            _ => eprintln!("Running with {} threads", num_threads),
+        }
         if cli.noclobber {
             eprintln!("Preserving output files");
         }
+        if cli.strict {
+            eprintln!("Exiting on first error");
+        }
     }
 
+    let start = Instant::now();
     match &cli.input {
         Input::Filter(filter_input) => execute_command(filter_input, &cli),
-        Input::Render(render_input) => execute_command(render_input, &cli),
+        Input::Render(render_input) => execute_command(render_input.as_ref(), &cli),
         Input::Stats(stats_input) => execute_command(stats_input, &cli),
     };
+    if cli.verbose {
+        eprintln!("Finished in {:.2}s", start.elapsed().as_secs_f64());
+    }
 }
 
 fn execute_command<I, C>(input: &I, cli: &Cli)
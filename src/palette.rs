@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::ffi::OsStr;
 use std::fs::OpenOptions;
 use std::io::Read;
@@ -6,8 +7,15 @@ use std::path::Path;
 use crate::error::{RuntimeResult, RuntimeError, RuntimeErrorKind};
 
 use hex::FromHex;
+use image::RgbaImage;
 use serde_json::Value;
 
+// Extensions understood by PaletteParser::try_parse
+pub const EXTENSIONS: [&str; 5] = ["json", "aco", "csv", "gpl", "txt"];
+
+// Upper bound of colors extracted from an image
+pub const MAX_IMAGE_COLORS: usize = 256;
+
 pub struct PaletteParser {}
 
 impl PaletteParser {
@@ -23,10 +31,27 @@ impl PaletteParser {
             Some("csv") => Ok(Self::parse_csv(&mut file)?),
             Some("gpl") => Ok(Self::parse_gpl(&mut file)?),
             Some("txt") => Ok(Self::parse_txt(&mut file)?),
-            _ => Err(RuntimeError::new(RuntimeErrorKind::Unsupported)),
+            _ => Err(RuntimeError::new(RuntimeErrorKind::UnsupportedExtension(&EXTENSIONS))),
         }.map_err(|e| RuntimeError::from_err(e, path, 0))
     }
 
+    pub fn try_parse_image(path: &str) -> RuntimeResult<Vec<[u8; 4]>> {
+        let image = image::open(path).map_err(|e| RuntimeError::from_err(e, path, 0))?;
+        Ok(Self::from_image(&image.into_rgba8(), MAX_IMAGE_COLORS))
+    }
+
+    // Unique opaque colors of an image, most frequent first (ties are ordered by color)
+    pub fn from_image(image: &RgbaImage, max_colors: usize) -> Vec<[u8; 4]> {
+        let mut counts: HashMap<[u8; 4], usize> = HashMap::new();
+        for pixel in image.pixels().filter(|p| p.0[3] != 0) {
+            *counts.entry(pixel.0).or_default() += 1;
+        }
+
+        let mut colors: Vec<([u8; 4], usize)> = counts.into_iter().collect();
+        colors.sort_unstable_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        colors.into_iter().take(max_colors).map(|(color, _)| color).collect()
+    }
+
     // TODO: Improve (?)
     pub fn parse_json<R>(input: &mut R) -> RuntimeResult<Vec<[u8; 4]>>
     where
@@ -190,3 +215,54 @@ impl PaletteParser {
         Ok(rgba)
     }
 }
+
+// Reads "name,index" or "name,#hex" lines (e.g. the pxls palette csv), hex colors must exist in the palette
+// The first line is treated as a header if it cannot be parsed
+pub fn parse_names(path: &str, palette: &[[u8; 4]]) -> RuntimeResult<HashMap<usize, String>> {
+    let mut buffer = String::new();
+    OpenOptions::new()
+        .read(true)
+        .open(path)
+        .and_then(|mut file| file.read_to_string(&mut buffer))
+        .map_err(|e| RuntimeError::from_err(e, path, 0))?;
+
+    let mut names = HashMap::new();
+    for (i, line) in buffer.lines().enumerate().filter(|(_, l)| !l.trim().is_empty()) {
+        let mut values = line.split(',').map(str::trim);
+        let name = values.next().unwrap_or_default();
+        let value = values.next().unwrap_or_default();
+        let index = match value.strip_prefix('#') {
+            Some(hex) => <[u8; 3]>::from_hex(hex)
+                .ok()
+                .and_then(|rgb| palette.iter().position(|p| p[..3] == rgb)),
+            None => value.parse::<usize>().ok(),
+        };
+
+        match index {
+            Some(index) => {
+                names.insert(index, name.to_owned());
+            }
+            None if i == 0 => (),
+            None => Err(RuntimeError::new_with_file(
+                RuntimeErrorKind::BadToken(value.to_owned()),
+                path,
+                i + 1,
+            ))?,
+        }
+    }
+
+    Ok(names)
+}
+
+// Index of the closest palette color (Euclidean distance in RGB)
+pub fn nearest_index(palette: &[[u8; 4]], color: [u8; 4]) -> Option<usize> {
+    palette
+        .iter()
+        .enumerate()
+        .min_by_key(|(_, p)| {
+            (0..3)
+                .map(|i| (p[i] as i32 - color[i] as i32).pow(2))
+                .sum::<i32>()
+        })
+        .map(|(i, _)| i)
+}
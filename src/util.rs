@@ -1,5 +1,169 @@
+use std::io::{BufRead, BufReader, Read};
+
+use chrono::{Duration, NaiveDate, NaiveDateTime};
+use hex::FromHex;
 use num_traits::{Bounded, NumOps};
 
+use crate::error::{RuntimeError, RuntimeErrorKind, RuntimeResult};
+
+// Reads a log from a filepath or a http(s) url (Requires the "url" feature)
+// Actions borrow from the returned data, so urls are downloaded in full before parsing
+pub fn read_log(src: &str) -> RuntimeResult<String> {
+    let mut data = String::new();
+    open_log(src)?.read_to_string(&mut data)?;
+    Ok(data)
+}
+
+// Opens a log from a filepath or a http(s) url for reading line by line, urls are streamed
+pub fn open_log(src: &str) -> RuntimeResult<Box<dyn BufRead>> {
+    if src.starts_with("http://") || src.starts_with("https://") {
+        Ok(Box::new(BufReader::new(read_url(src)?)))
+    } else {
+        Ok(Box::new(BufReader::new(std::fs::File::open(src)?)))
+    }
+}
+
+#[cfg(feature = "url")]
+fn read_url(url: &str) -> RuntimeResult<impl Read> {
+    Ok(ureq::get(url).call()?.into_reader())
+}
+
+#[cfg(not(feature = "url"))]
+fn read_url(_url: &str) -> RuntimeResult<std::io::Empty> {
+    Err(RuntimeError::new(RuntimeErrorKind::UrlUnsupported))
+}
+
+// Parses "#RRGGBB" or "#RRGGBBAA" (The '#' is optional)
+pub fn parse_color(s: &str) -> Result<[u8; 4], String> {
+    let hex = s.strip_prefix('#').unwrap_or(s);
+    let rgba = match hex.len() {
+        6 => <[u8; 3]>::from_hex(hex).map(|rgb| [rgb[0], rgb[1], rgb[2], 255]),
+        8 => <[u8; 4]>::from_hex(hex),
+        _ => Err(hex::FromHexError::InvalidStringLength),
+    };
+    rgba.map_err(|e| format!("{} (expected #RRGGBB or #RRGGBBAA)", e))
+}
+
+// Parses a duration into milliseconds, plain numbers are already milliseconds (e.g. "500", "5m", "1.5h", "2d")
+pub fn parse_duration(s: &str) -> Result<i64, String> {
+    let (value, unit) = s.split_at(s.find(char::is_alphabetic).unwrap_or(s.len()));
+    let unit = match unit {
+        "" => return value.parse::<i64>().map_err(|e| e.to_string()),
+        "ms" => Duration::milliseconds(1),
+        "s" => Duration::seconds(1),
+        "m" => Duration::minutes(1),
+        "h" => Duration::hours(1),
+        "d" => Duration::days(1),
+        "w" => Duration::weeks(1),
+        _ => return Err(format!("unknown unit '{}' (expected ms, s, m, h, d or w)", unit)),
+    };
+    let ms = value.parse::<f64>().map_err(|e| e.to_string())? * unit.num_milliseconds() as f64;
+    match ms.is_finite() && ms.abs() < i64::MAX as f64 {
+        true => Ok(ms.round() as i64),
+        false => Err(String::from("duration is out of range")),
+    }
+}
+
+// Parses a timestamp with a "T" or space separator, optional seconds and fraction, or a date alone (midnight)
+// Log timestamps (e.g. "2022-05-01 10:00:00,000") are accepted as is
+pub fn parse_timestamp(s: &str) -> Result<NaiveDateTime, String> {
+    const FORMATS: [&str; 5] = [
+        "%Y-%m-%dT%H:%M:%S%.f",
+        "%Y-%m-%d %H:%M:%S%.f",
+        "%Y-%m-%d %H:%M:%S,%3f",
+        "%Y-%m-%dT%H:%M",
+        "%Y-%m-%d %H:%M",
+    ];
+    FORMATS
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(s, format).ok())
+        .or_else(|| {
+            NaiveDate::parse_from_str(s, "%Y-%m-%d")
+                .ok()
+                .and_then(|date| date.and_hms_opt(0, 0, 0))
+        })
+        .ok_or_else(|| {
+            String::from("expected e.g. \"2022-05-01T10:00:00\", \"2022-05-01 10:00\" or \"2022-05-01\"")
+        })
+}
+
+// Small seeded PRNG (SplitMix64), reproducible across platforms
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        Rng { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E3779B97F4A7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+        z ^ (z >> 31)
+    }
+
+    // Uniform value in 0..n
+    pub fn below(&mut self, n: usize) -> usize {
+        ((self.next_u64() as u128 * n as u128) >> 64) as usize
+    }
+}
+
+// Matches text against a pattern where '*' is any sequence and '?' is any character
+#[derive(Debug, Clone)]
+pub struct Glob {
+    pattern: Vec<char>,
+    ignore_case: bool,
+}
+
+impl Glob {
+    pub fn new(pattern: &str, ignore_case: bool) -> Glob {
+        let pattern = match ignore_case {
+            true => pattern.to_lowercase(),
+            false => pattern.to_owned(),
+        };
+        Glob {
+            pattern: pattern.chars().collect(),
+            ignore_case,
+        }
+    }
+
+    pub fn matches(&self, text: &str) -> bool {
+        let text: Vec<char> = match self.ignore_case {
+            true => text.to_lowercase().chars().collect(),
+            false => text.chars().collect(),
+        };
+
+        // Greedy match, backtracking to the last '*' on mismatch
+        let (mut p, mut t) = (0, 0);
+        let mut star = None;
+        while t < text.len() {
+            match self.pattern.get(p) {
+                Some('*') => {
+                    star = Some((p, t));
+                    p += 1;
+                }
+                Some(c) if *c == '?' || *c == text[t] => {
+                    p += 1;
+                    t += 1;
+                }
+                _ => match star {
+                    Some((star_p, star_t)) => {
+                        p = star_p + 1;
+                        t = star_t + 1;
+                        star = Some((star_p, star_t + 1));
+                    }
+                    None => return false,
+                },
+            }
+        }
+
+        self.pattern[p..].iter().all(|c| *c == '*')
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct Region<T> {
     start: (T, T),
@@ -0,0 +1,94 @@
+mod common;
+
+use assert_cmd::Command;
+use predicates::prelude::*;
+
+use common::{tmp_path, write_log, SAMPLE_LOG};
+
+// Exit codes from the exitcode crate (sysexits.h) and clap
+const USAGE: i32 = 64;
+const DATAERR: i32 = 65;
+const NOINPUT: i32 = 66;
+const CLAP_ERROR: i32 = 2;
+
+fn cmd() -> Command {
+    Command::cargo_bin("pxlslog-explorer").unwrap()
+}
+
+#[test]
+fn filter_combined_args() {
+    let src = write_log("cli_filter.log", SAMPLE_LOG);
+    let dst = tmp_path("cli_filter_out.log");
+    cmd()
+        .args(["filter", "-s", src.to_str().unwrap(), "-d", dst.to_str().unwrap()])
+        .args(["--region", "0", "0", "3", "3"])
+        .args(["--color", "5", "22"])
+        .args(["--after", "2022-01-01T00:00:00.100"])
+        .assert()
+        .success();
+
+    let lines: Vec<&str> = SAMPLE_LOG.lines().collect();
+    let expected = format!("{}\n{}\n", lines[0], lines[2]);
+    assert_eq!(std::fs::read_to_string(dst).unwrap(), expected);
+}
+
+#[test]
+fn render_screenshot() {
+    let src = write_log("cli_render.log", SAMPLE_LOG);
+    let dst = tmp_path("cli_render.png");
+    let args = ["render", "--screenshot", "-s", src.to_str().unwrap(), "--size", "4", "4"];
+
+    cmd().args(args).args(["-d", dst.to_str().unwrap()]).assert().success();
+    let frame = image::open(tmp_path("cli_render_0.png")).unwrap();
+    assert_eq!((frame.width(), frame.height()), (4, 4));
+
+    // Raw RGBA frames without a destination
+    cmd()
+        .args(args)
+        .assert()
+        .success()
+        .stdout(predicate::function(|out: &[u8]| out.len() == 4 * 4 * 4));
+}
+
+#[test]
+fn exit_codes() {
+    let missing = tmp_path("cli_missing.log");
+    cmd()
+        .args(["filter", "-s", missing.to_str().unwrap()])
+        .assert()
+        .code(NOINPUT);
+
+    let src = write_log("cli_exit_codes.log", SAMPLE_LOG);
+    let src = src.to_str().unwrap();
+    let render = ["render", "-s", src, "--size", "4", "4"];
+    cmd()
+        .args(render)
+        .args(["--screenshot", "--limit", "0"])
+        .assert()
+        .code(USAGE);
+
+    cmd()
+        .args(render)
+        .args(["--screenshot", "--style", "x"])
+        .assert()
+        .code(CLAP_ERROR);
+
+    cmd()
+        .args(render)
+        .args(["--skip", "2", "--pipe", "-d", "/dev/null"])
+        .assert()
+        .code(USAGE)
+        .stderr(predicate::str::contains("Skipping every frame"));
+
+    cmd()
+        .args(render)
+        .args(["--screenshot", "--end-hold", "1000"])
+        .assert()
+        .code(CLAP_ERROR);
+
+    let invalid = write_log("cli_invalid.log", "not a log\n");
+    cmd()
+        .args(["--strict", "filter", "-s", invalid.to_str().unwrap()])
+        .assert()
+        .code(DATAERR);
+}
@@ -0,0 +1,58 @@
+#![allow(dead_code)]
+
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+use image::RgbaImage;
+
+// Small deterministic log covering multiple action kinds
+pub const SAMPLE_LOG: &str = "\
+2022-01-01 00:00:00,250\talice\t0\t0\t5\tuser place
+2022-01-01 00:00:00,500\tbob\t1\t0\t29\tuser place
+2022-01-01 00:00:01,000\talice\t2\t1\t22\tmod overwrite
+2022-01-01 00:00:02,000\tcarol\t3\t3\t15\tuser place
+";
+
+pub fn tmp_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(name)
+}
+
+pub fn write_log(name: &str, contents: &str) -> PathBuf {
+    let path = tmp_path(name);
+    std::fs::write(&path, contents).unwrap();
+    path
+}
+
+pub fn run(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_pxlslog-explorer"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+// Renders a single frame of the log and returns it
+pub fn screenshot(name: &str, log: &str, args: &[&str]) -> RgbaImage {
+    let src = write_log(&format!("{}.log", name), log);
+    let dst = tmp_path(&format!("{}.png", name));
+    let mut all_args = vec![
+        "render",
+        "--screenshot",
+        "-s",
+        src.to_str().unwrap(),
+        "-d",
+        dst.to_str().unwrap(),
+    ];
+    all_args.extend_from_slice(args);
+
+    let output = run(&all_args);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    // Frames are suffixed with their index
+    image::open(tmp_path(&format!("{}_0.png", name)))
+        .unwrap()
+        .to_rgba8()
+}
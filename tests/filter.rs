@@ -0,0 +1,188 @@
+mod common;
+
+use common::{run, tmp_path, write_log};
+
+const LOG: &str = "\
+2022-01-01 00:00:00,000\talice\t0\t0\t5\tuser place
+2022-01-01 00:00:01,000\tbob\t5\t5\t29\tuser place
+2022-01-01 00:00:02,000\tcarol\t9\t9\t22\tuser place
+";
+
+// Runs the filter over a log and returns the kept lines
+fn filter(name: &str, log: &str, args: &[&str]) -> String {
+    let src = write_log(&format!("{}.log", name), log);
+    let dst = tmp_path(&format!("{}_out.log", name));
+    let mut all_args = vec![
+        "filter",
+        "-s",
+        src.to_str().unwrap(),
+        "-d",
+        dst.to_str().unwrap(),
+    ];
+    all_args.extend_from_slice(args);
+
+    let output = run(&all_args);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    std::fs::read_to_string(dst).unwrap()
+}
+
+#[test]
+fn region_union() {
+    let args = ["--region", "0", "0", "2", "2", "--region", "8", "8", "2", "2"];
+    let out = filter("region_union", LOG, &args);
+    let lines: Vec<&str> = LOG.lines().collect();
+    assert_eq!(out, format!("{}\n{}\n", lines[0], lines[2]));
+
+    // Regions are grouped by occurrence, not by the total number of values
+    let src = write_log("region_partial.log", LOG);
+    let partial = ["--region", "1", "1", "--region", "2", "2"];
+    let output = run(&[&["filter", "-s", src.to_str().unwrap()][..], &partial].concat());
+    assert!(!output.status.success());
+}
+
+#[test]
+fn username() {
+    let out = filter("username", LOG, &["--username", "alice", "carol"]);
+    let lines: Vec<&str> = LOG.lines().collect();
+    assert_eq!(out, format!("{}\n{}\n", lines[0], lines[2]));
+}
+
+#[test]
+fn username_glob() {
+    let out = filter("username_glob", LOG, &["--username", "?Ar*", "--user-ignore-case"]);
+    let lines: Vec<&str> = LOG.lines().collect();
+    assert_eq!(out, format!("{}\n", lines[2]));
+}
+
+#[test]
+fn merge() {
+    let lines: Vec<&str> = LOG.lines().collect();
+    let a = write_log("merge_a.log", &format!("{}\n{}\n", lines[1], lines[2]));
+    let b = write_log("merge_b.log", &format!("{}\n{}\n", lines[0], lines[1]));
+    let dst = tmp_path("merge_out.log");
+    let output = run(&[
+        "filter",
+        "--merge",
+        a.to_str().unwrap(),
+        b.to_str().unwrap(),
+        "-d",
+        dst.to_str().unwrap(),
+    ]);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(std::fs::read_to_string(dst).unwrap(), LOG);
+}
+
+#[test]
+fn mixed_users_warning() {
+    let hash = "0".repeat(64);
+    let log = format!("{}2022-01-01 00:00:03,000\t{}\t1\t1\t5\tuser place\n", LOG, hash);
+    let src = write_log("mixed_users.log", &log);
+    let dst = tmp_path("mixed_users_out.log");
+    let output = run(&["filter", "-s", src.to_str().unwrap(), "-d", dst.to_str().unwrap()]);
+    assert!(output.status.success());
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("mixes usernames (3 actions) and hashes (1 actions)"), "{}", stderr);
+
+    // Consistent logs are not reported
+    let src = write_log("consistent_users.log", LOG);
+    let output = run(&["filter", "-s", src.to_str().unwrap(), "-d", dst.to_str().unwrap()]);
+    assert!(output.stderr.is_empty());
+}
+
+#[test]
+fn username_hash_boundaries() {
+    // Only 64 hex characters are a hash, hex-looking or 64 character usernames are still usernames
+    let names = ["a".repeat(32), "a".repeat(63), "g".repeat(64), "a".repeat(65)];
+    let log: String = names
+        .iter()
+        .map(|name| format!("2022-01-01 00:00:00,000\t{}\t0\t0\t5\tuser place\n", name))
+        .collect();
+    for name in &names {
+        let out = filter("username_hash_boundaries", &log, &["--username", name]);
+        assert_eq!(out.lines().count(), 1, "{}", name);
+    }
+
+    let hash = "a".repeat(64);
+    let log = format!("2022-01-01 00:00:00,000\t{}\t0\t0\t5\tuser place\n", hash);
+    let out = filter("username_hash", &log, &["--username", &hash]);
+    assert!(out.is_empty());
+}
+
+#[test]
+fn hash_src_rejects_usernames() {
+    let hashes = write_log("hash_src_valid.txt", &format!("{}\n", "0123456789abcdef".repeat(4)));
+    filter("hash_src_valid", LOG, &["--hash-src", hashes.to_str().unwrap()]);
+
+    let hashes = write_log("hash_src_invalid.txt", &format!("{}\n", "g".repeat(64)));
+    let src = write_log("hash_src_invalid.log", LOG);
+    let output = run(&[
+        "filter",
+        "-s",
+        src.to_str().unwrap(),
+        "--hash-src",
+        hashes.to_str().unwrap(),
+    ]);
+    assert!(!output.status.success());
+}
+
+#[test]
+fn on_reset() {
+    let log = format!("{}2021-01-01 00:00:00,000\tdave\t1\t1\t5\tuser place\n", LOG);
+    let src = write_log("on_reset.log", &log);
+    let dst = tmp_path("on_reset_out.log");
+    let args = ["filter", "-s", src.to_str().unwrap(), "-d", dst.to_str().unwrap()];
+
+    // Errors by default, reporting the line of the reset
+    let output = run(&args);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("at line 4"), "{}", stderr);
+
+    let output = run(&[&["--on-reset", "ignore"][..], &args].concat());
+    assert!(output.status.success());
+    assert_eq!(std::fs::read_to_string(&dst).unwrap(), log);
+
+    // Small backwards jumps are out of order writes rather than resets
+    let lines: Vec<&str> = LOG.lines().collect();
+    let shuffled = format!("{}\n{}\n{}\n", lines[2], lines[0], lines[1]);
+    assert_eq!(filter("on_reset_shuffled", &shuffled, &[]), shuffled);
+
+    // Each canvas is filtered into its own output
+    let output = run(&[&["--on-reset", "split"][..], &args].concat());
+    assert!(output.status.success());
+    let canvas = |n: usize| {
+        std::fs::read_to_string(tmp_path(&format!("on_reset_out_canvas{}.log", n))).unwrap()
+    };
+    assert_eq!(canvas(0), LOG);
+    assert_eq!(canvas(1), "2021-01-01 00:00:00,000\tdave\t1\t1\t5\tuser place\n");
+}
+
+#[test]
+fn timestamp_formats() {
+    let lines: Vec<&str> = LOG.lines().collect();
+    let expected = format!("{}\n{}\n", lines[1], lines[2]);
+    for after in [
+        "2022-01-01T00:00:00.500",
+        "2022-01-01 00:00:00.5",
+        "2022-01-01 00:00:00,500",
+    ] {
+        assert_eq!(filter("timestamp_formats", LOG, &["--after", after]), expected, "{}", after);
+    }
+
+    // Dates alone are midnight, minutes without seconds are accepted
+    let before = filter("timestamp_date", LOG, &["--before", "2022-01-01"]);
+    assert_eq!(before, format!("{}\n", lines[0]));
+    assert_eq!(filter("timestamp_minutes", LOG, &["--after", "2022-01-01T00:00"]), LOG);
+
+    let output = run(&["filter", "--after", "yesterday"]);
+    assert!(!output.status.success());
+}
@@ -0,0 +1,661 @@
+mod common;
+
+use common::{screenshot, SAMPLE_LOG};
+use image::RgbaImage;
+
+const WHITE: [u8; 4] = [255, 255, 255, 255];
+
+// Compares touched pixels against expected colors, every other pixel must match the background
+fn assert_pixels(frame: &RgbaImage, background: [u8; 4], expected: &[((u32, u32), [u8; 4])]) {
+    for (x, y, pixel) in frame.enumerate_pixels() {
+        let color = expected
+            .iter()
+            .find(|(pos, _)| *pos == (x, y))
+            .map(|(_, color)| *color)
+            .unwrap_or(background);
+        assert_eq!(pixel.0, color, "pixel ({}, {})", x, y);
+    }
+}
+
+fn render(name: &str, style: &str) -> RgbaImage {
+    let args = ["--size", "4", "4", "--color", "255", "255", "255", "255", "--style", style];
+    screenshot(name, SAMPLE_LOG, &args)
+}
+
+#[test]
+fn normal() {
+    let frame = render("golden_normal", "normal");
+    #[rustfmt::skip]
+    assert_pixels(&frame, WHITE, &[
+        ((0, 0), [255, 255, 255, 255]),
+        ((1, 0), [240, 37, 35, 255]),
+        ((2, 1), [18, 92, 199, 255]),
+        ((3, 3), [112, 221, 19, 255]),
+    ]);
+}
+
+#[test]
+fn normal_undo() {
+    let log = "\
+2022-01-01 00:00:00,000\talice\t0\t0\t5\tuser place
+2022-01-01 00:00:01,000\tbob\t0\t0\t29\tuser place
+2022-01-01 00:00:02,000\tbob\t0\t0\t29\tuser undo
+2022-01-01 00:00:03,000\tcarol\t1\t0\t22\tuser place
+2022-01-01 00:00:04,000\tcarol\t1\t0\t22\tuser undo
+";
+    let args = ["--size", "2", "1", "--color", "0", "0", "0", "255"];
+    let frame = screenshot("golden_normal_undo", log, &args);
+    assert_pixels(&frame, [0, 0, 0, 255], &[((0, 0), [255, 255, 255, 255])]);
+}
+
+#[test]
+fn normal_mod_actions() {
+    let log = "\
+2022-01-01 00:00:00,000\talice\t0\t0\t5\tuser place
+2022-01-01 00:00:00,000\talice\t1\t0\t5\tuser place
+2022-01-01 00:00:01,000\tbob\t0\t0\t29\tconsole nuke
+2022-01-01 00:00:02,000\tbob\t1\t0\t29\trollback
+";
+    let args = ["--size", "2", "1", "--color", "0", "0", "0", "255"];
+    let frame = screenshot("golden_normal_mod_actions", log, &args);
+    assert_pixels(&frame, [0, 0, 0, 255], &[((1, 0), [255, 255, 255, 255])]);
+}
+
+#[test]
+fn virgin() {
+    let frame = render("golden_virgin", "virgin");
+    #[rustfmt::skip]
+    assert_pixels(&frame, WHITE, &[
+        ((0, 0), [0, 0, 0, 255]),
+        ((1, 0), [0, 0, 0, 255]),
+        ((2, 1), [0, 0, 0, 255]),
+        ((3, 3), [0, 0, 0, 255]),
+    ]);
+}
+
+#[test]
+fn action() {
+    let frame = render("golden_action", "action");
+    #[rustfmt::skip]
+    assert_pixels(&frame, WHITE, &[
+        ((0, 0), [0, 0, 255, 255]),
+        ((1, 0), [0, 0, 255, 255]),
+        ((2, 1), [0, 255, 255, 255]),
+        ((3, 3), [0, 0, 255, 255]),
+    ]);
+}
+
+#[test]
+fn activity() {
+    let frame = render("golden_activity", "activity");
+    #[rustfmt::skip]
+    assert_pixels(&frame, [0, 0, 0, 255], &[
+        ((0, 0), [255, 0, 0, 255]),
+        ((1, 0), [255, 0, 0, 255]),
+        ((2, 1), [255, 0, 0, 255]),
+        ((3, 3), [255, 0, 0, 255]),
+    ]);
+}
+
+#[test]
+fn milliseconds() {
+    let frame = render("golden_milliseconds", "milliseconds");
+    #[rustfmt::skip]
+    assert_pixels(&frame, WHITE, &[
+        ((0, 0), [126, 0, 0, 255]),
+        ((1, 0), [254, 0, 0, 255]),
+        ((2, 1), [255, 254, 254, 255]),
+        ((3, 3), [255, 254, 254, 255]),
+    ]);
+}
+
+#[test]
+fn combined() {
+    let frame = render("golden_combined", "combined");
+    #[rustfmt::skip]
+    assert_pixels(&frame, WHITE, &[
+        ((0, 0), [63, 1, 0, 255]),
+        ((1, 0), [127, 2, 0, 255]),
+        ((2, 1), [254, 4, 0, 255]),
+        ((3, 3), [254, 8, 0, 255]),
+    ]);
+}
+
+#[test]
+fn order_within_frame() {
+    let sorted = "\
+2022-01-01 00:00:00,000\talice\t0\t0\t5\tuser place
+2022-01-01 00:00:01,000\tbob\t0\t0\t29\tuser place
+2022-01-01 00:00:02,000\tcarol\t0\t0\t22\tuser place
+";
+    let shuffled = "\
+2022-01-01 00:00:02,000\tcarol\t0\t0\t22\tuser place
+2022-01-01 00:00:00,000\talice\t0\t0\t5\tuser place
+2022-01-01 00:00:01,000\tbob\t0\t0\t29\tuser place
+";
+    let args = ["--size", "1", "1", "--color", "0", "0", "0", "255"];
+    let expected = screenshot("order_sorted", sorted, &args);
+    let frame = screenshot("order_shuffled", shuffled, &args);
+    assert_eq!(frame, expected);
+    assert_pixels(&frame, [0, 0, 0, 255], &[((0, 0), [18, 92, 199, 255])]);
+}
+
+#[test]
+fn milliseconds_alpha() {
+    let args = [
+        "--size",
+        "4",
+        "4",
+        "--color",
+        "255",
+        "255",
+        "255",
+        "255",
+        "--style",
+        "milliseconds",
+        "--placement-color",
+        "#FF000080",
+    ];
+    let frame = screenshot("golden_milliseconds_alpha", SAMPLE_LOG, &args);
+    #[rustfmt::skip]
+    assert_pixels(&frame, WHITE, &[
+        ((0, 0), [126, 0, 0, 128]),
+        ((1, 0), [254, 0, 0, 128]),
+        ((2, 1), [255, 254, 254, 128]),
+        ((3, 3), [255, 254, 254, 128]),
+    ]);
+}
+
+#[test]
+fn milliseconds_gamma_correct() {
+    // Placements at the start and middle of the period land exactly on the ramp stops
+    let log = "\
+2022-01-01 00:00:00,001\talice\t0\t0\t5\tuser place
+2022-01-01 00:00:00,501\tbob\t1\t0\t5\tuser place
+2022-01-01 00:00:00,251\tcarol\t2\t0\t5\tuser place
+";
+    let args = ["--size", "3", "1", "--style", "milliseconds"];
+    let frame = screenshot("golden_milliseconds_linear", log, &args);
+    let args = [&args[..], &["--gamma-correct"]].concat();
+    let corrected = screenshot("golden_milliseconds_gamma_correct", log, &args);
+
+    assert_eq!(corrected.get_pixel(0, 0).0, [0, 0, 0, 255]);
+    assert_eq!(corrected.get_pixel(1, 0).0, [255, 0, 0, 255]);
+    assert_eq!(corrected.get_pixel(0, 0), frame.get_pixel(0, 0));
+    assert_eq!(corrected.get_pixel(1, 0), frame.get_pixel(1, 0));
+    assert_ne!(corrected.get_pixel(2, 0), frame.get_pixel(2, 0));
+}
+
+#[test]
+fn owner_territory_borders() {
+    let log = "\
+2022-01-01 00:00:00,000\talice\t0\t0\t5\tuser place
+2022-01-01 00:00:00,000\talice\t1\t0\t5\tuser place
+2022-01-01 00:00:00,000\tbob\t2\t0\t5\tuser place
+2022-01-01 00:00:00,000\tbob\t3\t0\t5\tuser place
+";
+    let args = ["--size", "4", "1", "--style", "owner"];
+    let frame = screenshot("golden_owner", log, &args);
+    assert_eq!(frame.get_pixel(0, 0), frame.get_pixel(1, 0));
+    assert_eq!(frame.get_pixel(2, 0), frame.get_pixel(3, 0));
+    assert_ne!(frame.get_pixel(1, 0), frame.get_pixel(2, 0));
+
+    let args = [&args[..], &["--territory-borders"]].concat();
+    let bordered = screenshot("golden_owner_borders", log, &args);
+    assert_eq!(bordered.get_pixel(0, 0), frame.get_pixel(0, 0));
+    assert_eq!(bordered.get_pixel(1, 0).0, [0, 0, 0, 255]);
+    assert_eq!(bordered.get_pixel(2, 0).0, [0, 0, 0, 255]);
+    assert_eq!(bordered.get_pixel(3, 0), frame.get_pixel(3, 0));
+}
+
+#[test]
+fn owner_user_color() {
+    let args = ["--size", "4", "4", "--style", "owner", "--user-color", "bob=#00FF00"];
+    let frame = screenshot("golden_owner_user_color", SAMPLE_LOG, &args);
+    assert_eq!(frame.get_pixel(1, 0).0, [0, 255, 0, 255]);
+    assert_ne!(frame.get_pixel(0, 0).0, [0, 255, 0, 255]);
+}
+
+#[test]
+fn small_background() {
+    let bg = common::tmp_path("small_background_bg.png");
+    RgbaImage::from_pixel(2, 2, image::Rgba(WHITE)).save(&bg).unwrap();
+    let log = "2022-01-01 00:00:00,000\talice\t0\t0\t29\tuser place\n";
+
+    // Uncovered pixels are transparent unless a color is given
+    let args = ["-b", bg.to_str().unwrap(), "--size", "3", "3"];
+    let frame = screenshot("small_background", log, &args);
+    assert_eq!(frame.dimensions(), (3, 3));
+    #[rustfmt::skip]
+    assert_pixels(&frame, [0, 0, 0, 0], &[
+        ((0, 0), [240, 37, 35, 255]),
+        ((1, 0), WHITE),
+        ((0, 1), WHITE),
+        ((1, 1), WHITE),
+    ]);
+
+    let args = [&args[..], &["--color", "255", "0", "0", "255"]].concat();
+    let frame = screenshot("small_background_color", log, &args);
+    #[rustfmt::skip]
+    assert_pixels(&frame, [255, 0, 0, 255], &[
+        ((0, 0), [240, 37, 35, 255]),
+        ((1, 0), WHITE),
+        ((0, 1), WHITE),
+        ((1, 1), WHITE),
+    ]);
+}
+
+#[test]
+fn cropped_background_origin() {
+    // Actions and the background are both offset by the crop origin
+    let bg = common::tmp_path("cropped_background_bg.png");
+    let mut image = RgbaImage::from_pixel(6, 6, image::Rgba(WHITE));
+    image.put_pixel(4, 4, image::Rgba([0, 0, 255, 255]));
+    image.save(&bg).unwrap();
+    let log = "2022-01-01 00:00:00,000\talice\t5\t5\t29\tuser place\n";
+
+    let args = ["-b", bg.to_str().unwrap(), "--crop", "4", "4", "2", "2"];
+    let frame = screenshot("cropped_background", log, &args);
+    assert_eq!(frame.dimensions(), (2, 2));
+    #[rustfmt::skip]
+    assert_pixels(&frame, WHITE, &[
+        ((0, 0), [0, 0, 255, 255]),
+        ((1, 1), [240, 37, 35, 255]),
+    ]);
+}
+
+#[test]
+fn palette_from_image() {
+    // Most frequent color first, transparent pixels are ignored
+    let source = common::tmp_path("palette_from_image.png");
+    let mut image = RgbaImage::new(4, 1);
+    image.put_pixel(0, 0, image::Rgba([0, 0, 255, 255]));
+    image.put_pixel(1, 0, image::Rgba([255, 0, 0, 255]));
+    image.put_pixel(2, 0, image::Rgba([255, 0, 0, 255]));
+    image.save(&source).unwrap();
+    let log = "\
+2022-01-01 00:00:00,000\talice\t0\t0\t0\tuser place
+2022-01-01 00:00:01,000\tbob\t1\t0\t1\tuser place
+";
+
+    let args = ["--size", "3", "1", "--palette-from-image", source.to_str().unwrap()];
+    let frame = screenshot("palette_from_image", log, &args);
+    #[rustfmt::skip]
+    assert_pixels(&frame, [0, 0, 0, 255], &[
+        ((0, 0), [255, 0, 0, 255]),
+        ((1, 0), [0, 0, 255, 255]),
+    ]);
+}
+
+#[test]
+fn indexed_palette_limit() {
+    // 256 unique colors leave no slot for transparent pixels
+    let source = common::tmp_path("indexed_palette_limit_palette.png");
+    RgbaImage::from_fn(16, 16, |x, y| image::Rgba([(x * 16 + y) as u8, 0, 0, 255]))
+        .save(&source)
+        .unwrap();
+    let src = common::write_log("indexed_palette_limit.log", SAMPLE_LOG);
+    let dst = common::tmp_path("indexed_palette_limit.png");
+
+    let output = common::run(&[
+        "render",
+        "--screenshot",
+        "--indexed",
+        "-s",
+        src.to_str().unwrap(),
+        "-d",
+        dst.to_str().unwrap(),
+        "--size",
+        "4",
+        "4",
+        "--palette-from-image",
+        source.to_str().unwrap(),
+    ]);
+    assert_eq!(output.status.code(), Some(64));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("at most 255 palette colors"));
+}
+
+#[test]
+fn no_background() {
+    let bg = common::tmp_path("no_background_bg.png");
+    RgbaImage::from_pixel(2, 2, image::Rgba(WHITE)).save(&bg).unwrap();
+    let log = "\
+2022-01-01 00:00:00,000\talice\t0\t0\t29\tuser place
+2022-01-01 00:00:01,000\tbob\t1\t1\t29\tuser place
+2022-01-01 00:00:02,000\tbob\t1\t1\t29\tuser undo
+";
+
+    // The background only sets the size, undos restore transparency
+    let args = ["-b", bg.to_str().unwrap(), "--no-background"];
+    let frame = screenshot("no_background", log, &args);
+    assert_eq!(frame.dimensions(), (2, 2));
+    assert_pixels(&frame, [0, 0, 0, 0], &[((0, 0), [240, 37, 35, 255])]);
+}
+
+#[test]
+fn activity_kind_heatmap() {
+    let log = "\
+2022-01-01 00:00:00,000\talice\t0\t0\t5\tuser place
+2022-01-01 00:00:01,000\talice\t0\t0\t5\tuser place
+2022-01-01 00:00:02,000\tbob\t1\t0\t29\tconsole nuke
+";
+    let args = ["--size", "2", "1", "--style", "activity", "--kind-heatmap", "nuke"];
+    let frame = screenshot("golden_activity_kind_heatmap", log, &args);
+    assert_pixels(&frame, [0, 0, 0, 255], &[((1, 0), [255, 0, 0, 255])]);
+}
+
+#[test]
+fn milliseconds_dither() {
+    let args = ["--size", "4", "4", "--style", "milliseconds"];
+    let frame = screenshot("golden_milliseconds_plain", SAMPLE_LOG, &args);
+    let args = [&args[..], &["--dither"]].concat();
+    let dithered = screenshot("golden_milliseconds_dither", SAMPLE_LOG, &args);
+    let again = screenshot("golden_milliseconds_dither_again", SAMPLE_LOG, &args);
+    assert_eq!(dithered, again);
+    assert_ne!(dithered, frame);
+}
+
+#[test]
+fn on_reset_split() {
+    // The second canvas starts over a year earlier
+    let log = "\
+2022-01-01 00:00:00,000\talice\t0\t0\t29\tuser place
+2021-01-01 00:00:00,000\tbob\t1\t0\t29\tuser place
+";
+    let src = common::write_log("on_reset_split.log", log);
+    let dst = common::tmp_path("on_reset_split.png");
+    let output = common::run(&[
+        "--on-reset",
+        "split",
+        "render",
+        "--screenshot",
+        "-s",
+        src.to_str().unwrap(),
+        "-d",
+        dst.to_str().unwrap(),
+        "--size",
+        "2",
+        "1",
+    ]);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let open = |name: &str| image::open(common::tmp_path(name)).unwrap().to_rgba8();
+    let first = open("on_reset_split_canvas0_0.png");
+    assert_pixels(&first, [0, 0, 0, 255], &[((0, 0), [240, 37, 35, 255])]);
+    let second = open("on_reset_split_canvas1_0.png");
+    assert_pixels(&second, [0, 0, 0, 255], &[((1, 0), [240, 37, 35, 255])]);
+}
+
+#[test]
+fn fill_gaps_crossfade() {
+    // Three empty seconds between the placements
+    let log = "\
+2022-01-01 00:00:00,000\talice\t0\t0\t29\tuser place
+2022-01-01 00:00:04,000\tbob\t0\t0\t5\tuser place
+";
+    let src = common::write_log("fill_gaps.log", log);
+    let dst = common::tmp_path("fill_gaps.png");
+    let output = common::run(&[
+        "render",
+        "-s",
+        src.to_str().unwrap(),
+        "-d",
+        dst.to_str().unwrap(),
+        "--size",
+        "1",
+        "1",
+        "--step",
+        "1000",
+        "--fill-gaps",
+        "crossfade",
+    ]);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let pixel = |i: usize| {
+        let path = common::tmp_path(&format!("fill_gaps_{}.png", i));
+        image::open(path).unwrap().to_rgba8().get_pixel(0, 0).0
+    };
+    assert_eq!(pixel(1), [240, 37, 35, 255]);
+    assert_eq!(pixel(3), [247, 146, 145, 255]);
+    assert_eq!(pixel(5), WHITE);
+}
+
+// Number of frames written by a render of the log
+fn count_frames(name: &str, log: &str, args: &[&str]) -> usize {
+    let src = common::write_log(&format!("{}.log", name), log);
+    let dst = common::tmp_path(&format!("{}.png", name));
+    let mut all_args = vec!["render", "-s", src.to_str().unwrap(), "-d", dst.to_str().unwrap()];
+    all_args.extend_from_slice(args);
+    let output = common::run(&all_args);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    (0..)
+        .take_while(|i| common::tmp_path(&format!("{}_{}.png", name, i)).exists())
+        .count()
+}
+
+#[test]
+fn calendar_steps() {
+    // Sunday late evening, Monday morning and Wednesday
+    let log = "\
+2022-01-02 23:59:00,000\talice\t0\t0\t29\tuser place
+2022-01-03 00:01:00,000\tbob\t1\t0\t29\tuser place
+2022-01-05 12:00:00,000\tcarol\t0\t0\t5\tuser place
+";
+    let args = ["--size", "2", "1"];
+    // Background, then the 2nd to the 5th including the empty 4th
+    let daily = [&args[..], &["--daily"]].concat();
+    assert_eq!(count_frames("calendar_daily", log, &daily), 5);
+    // Weeks start on Monday
+    let weekly = [&args[..], &["--weekly"]].concat();
+    assert_eq!(count_frames("calendar_weekly", log, &weekly), 3);
+}
+
+#[test]
+fn step_units() {
+    let log = "\
+2022-01-01 00:00:00,000\talice\t0\t0\t29\tuser place
+2022-01-01 00:03:00,000\tbob\t1\t0\t29\tuser place
+";
+    let frames = |name: &str, step: &str| {
+        count_frames(name, log, &["--size", "2", "1", "--step", step])
+    };
+    assert_eq!(frames("step_units_ms", "60000"), frames("step_units_minutes", "1m"));
+    assert_eq!(frames("step_units_fraction", "0.5h"), frames("step_units_hours_ms", "1800000"));
+
+    let output = common::run(&["render", "--size", "2", "1", "--step", "5x"]);
+    assert!(!output.status.success());
+}
+
+#[test]
+fn dump_actions() {
+    // Out of order and cropped actions are dumped as rendered
+    let log = "\
+2022-01-01 00:00:02,000\tcarol\t1\t1\t22\tuser place
+2022-01-01 00:00:00,000\talice\t0\t0\t5\tuser place
+2022-01-01 00:00:01,000\tbob\t5\t5\t29\tuser place
+";
+    let dump = common::tmp_path("dump_actions_out.log");
+    let args = ["--size", "2", "2", "--crop", "0", "0", "2", "2", "--dump-actions"];
+    let args = [&args[..], &[dump.to_str().unwrap()]].concat();
+    screenshot("dump_actions", log, &args);
+
+    let lines: Vec<&str> = log.lines().collect();
+    let expected = format!("{}\n{}\n", lines[1], lines[0]);
+    assert_eq!(std::fs::read_to_string(dump).unwrap(), expected);
+}
+
+#[test]
+fn step_boundary_undo() {
+    // The undo starts a new frame, its placement must only be applied once
+    let log = "\
+2022-01-01 00:00:00,000\talice\t0\t0\t5\tuser place
+2022-01-01 00:00:00,500\tbob\t0\t0\t29\tuser place
+2022-01-01 00:00:01,000\tbob\t0\t0\t29\tuser undo
+";
+    let args = ["--size", "1", "1"];
+    let stepped = [&args[..], &["--step", "1000"]].concat();
+    let frames = count_frames("step_boundary_undo", log, &stepped);
+    let last = common::tmp_path(&format!("step_boundary_undo_{}.png", frames - 1));
+    let last = image::open(last).unwrap().to_rgba8();
+
+    let final_frame = screenshot("step_boundary_undo_screenshot", log, &args);
+    assert_eq!(last, final_frame);
+    assert_eq!(last.get_pixel(0, 0).0, WHITE);
+}
+
+// Actions per frame listed in the manifest of a pixel stepped render
+fn pixel_chunks(name: &str, step: &str, ramp: &str) -> Vec<u64> {
+    let log: String = (0..10)
+        .map(|i| format!("2022-01-01 00:00:00,{:03}\talice\t{}\t0\t5\tuser place\n", i, i))
+        .collect();
+    let manifest = common::tmp_path(&format!("{}.json", name));
+    let args = [
+        "--size",
+        "10",
+        "1",
+        "--step-type",
+        "pixels",
+        "--step",
+        step,
+        "--pixel-ramp",
+        ramp,
+        "--manifest",
+        manifest.to_str().unwrap(),
+    ];
+    count_frames(name, &log, &args);
+
+    let manifest: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(manifest).unwrap()).unwrap();
+    manifest
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|frame| frame["actions"].as_u64().unwrap())
+        .collect()
+}
+
+#[test]
+fn pixel_ramp_chunks() {
+    // The background frame comes first, chunks never overlap and the remainder is the final frame
+    assert_eq!(pixel_chunks("pixel_ramp_fixed", "2", "1"), [0, 2, 2, 2, 2, 2]);
+    assert_eq!(pixel_chunks("pixel_ramp_grow", "1", "2"), [0, 1, 2, 4, 3]);
+    // Shrinking chunks hold at least one action
+    assert_eq!(pixel_chunks("pixel_ramp_shrink", "4", "0.5"), [0, 4, 2, 1, 1, 1, 1]);
+
+    let src = common::write_log("pixel_ramp_limit.log", SAMPLE_LOG);
+    let dst = common::tmp_path("pixel_ramp_limit.png");
+    let output = common::run(&[
+        "render",
+        "-s",
+        src.to_str().unwrap(),
+        "-d",
+        dst.to_str().unwrap(),
+        "--size",
+        "4",
+        "4",
+        "--step-type",
+        "pixels",
+        "--step",
+        "1",
+        "--max-frames",
+        "3",
+    ]);
+    assert_eq!(output.status.code(), Some(64));
+}
+
+#[test]
+fn compare_placed_only() {
+    let log = "2022-01-01 00:00:00,000\talice\t0\t0\t29\tuser place\n";
+    let src = common::write_log("compare_placed_only.log", log);
+    // The second pixel is never placed so differs only from the background
+    let reference = common::tmp_path("compare_placed_only_reference.png");
+    RgbaImage::from_fn(2, 1, |x, _| match x {
+        0 => image::Rgba([240, 37, 35, 255]),
+        _ => image::Rgba(WHITE),
+    })
+    .save(&reference)
+    .unwrap();
+
+    let output = common::run(&[
+        "render",
+        "-s",
+        src.to_str().unwrap(),
+        "--size",
+        "2",
+        "1",
+        "--compare",
+        reference.to_str().unwrap(),
+    ]);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("Matching: 1 of 1 placed pixels"), "{}", stdout);
+}
+
+#[test]
+fn heat_daily() {
+    // Heat is measured back from the end of each day
+    let log = "\
+2022-01-01 23:59:00,000\talice\t0\t0\t29\tuser place
+2022-01-02 23:55:00,000\tbob\t1\t0\t29\tuser place
+";
+    let args = ["--size", "2", "1", "--style", "heat", "--daily"];
+    let frames = count_frames("heat_daily", log, &args);
+    let last = common::tmp_path(&format!("heat_daily_{}.png", frames - 1));
+    let last = image::open(last).unwrap().to_rgba8();
+
+    assert_eq!(last.get_pixel(0, 0).0, [0, 0, 0, 255]);
+    assert_ne!(last.get_pixel(1, 0).0, [0, 0, 0, 255]);
+}
+
+#[test]
+fn apng_end_hold() {
+    let src = common::write_log("apng_end_hold.log", SAMPLE_LOG);
+    let dst = common::tmp_path("apng_end_hold.png");
+    let output = common::run(&[
+        "render",
+        "-s",
+        src.to_str().unwrap(),
+        "-d",
+        dst.to_str().unwrap(),
+        "--size",
+        "4",
+        "4",
+        "--step",
+        "0",
+        "--apng",
+        "--end-hold",
+        "2m",
+    ]);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    // Holds beyond 65s fall back to whole seconds
+    let decoder = png::Decoder::new(std::fs::File::open(dst).unwrap());
+    let mut reader = decoder.read_info().unwrap();
+    let mut buf = vec![0; reader.output_buffer_size()];
+    let mut delays = vec![];
+    while reader.next_frame(&mut buf).is_ok() {
+        let control = reader.info().frame_control().unwrap();
+        delays.push((control.delay_num, control.delay_den));
+    }
+    assert_eq!(delays, [(100, 1000), (120, 1)]);
+}
@@ -0,0 +1,128 @@
+mod common;
+
+use common::{run, write_log};
+
+// Runs the stats command and returns its output
+fn stats(name: &str, log: &str, args: &[&str]) -> String {
+    let src = write_log(&format!("{}.log", name), log);
+    let mut all_args = vec!["stats", "-s", src.to_str().unwrap()];
+    all_args.extend_from_slice(args);
+
+    let output = run(&all_args);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn personal_survival() {
+    let log = "\
+2022-01-01 00:00:00,000\tbob\t0\t0\t5\tuser place
+2022-01-01 00:00:01,000\talice\t0\t0\t29\tuser place
+2022-01-01 00:00:02,000\talice\t0\t0\t29\tuser undo
+2022-01-01 00:00:03,000\tbob\t1\t0\t5\tuser place
+2022-01-01 00:00:04,000\talice\t1\t0\t22\tmod overwrite
+2022-01-01 00:00:05,000\tbob\t2\t0\t5\tuser place
+2022-01-01 00:00:06,000\tbob\t2\t0\t5\tuser undo
+2022-01-01 00:00:07,000\tbob\t3\t0\t5\tuser place
+";
+    let args = ["-m", "personal", "--user", "bob", "--format", "csv"];
+    let out = stats("personal_survival", log, &args);
+    let counts: Vec<(&str, &str)> = out
+        .lines()
+        .skip(1)
+        .take_while(|l| !l.is_empty())
+        .map(|l| {
+            let mut row = l.split(',');
+            (row.next().unwrap(), row.next().unwrap())
+        })
+        .collect();
+
+    #[rustfmt::skip]
+    assert_eq!(counts, [
+        ("Total", "5"),
+        ("Placed", "4"),
+        ("Survived", "2"),
+        ("Replaced", "1"),
+        ("Replaced by self", "0"),
+        ("Replaced by mods", "1"),
+        ("Restored by mods", "0"),
+        ("Undone", "1"),
+    ]);
+}
+
+#[test]
+fn transparent_colors() {
+    let log = "\
+2022-01-01 00:00:00,000\tbob\t0\t0\t0\tuser place
+2022-01-01 00:00:01,000\tbob\t1\t0\t-1\tuser place
+2022-01-01 00:00:02,000\tbob\t2\t0\t-1\tuser place
+2022-01-01 00:00:03,000\tbob\t3\t0\t-1\tuser place
+2022-01-01 00:00:04,000\tbob\t4\t0\t5\tuser place
+";
+    let out = stats("transparent_colors", log, &["-m", "color", "--format", "csv"]);
+    let rows: Vec<&str> = out.lines().skip(1).collect();
+
+    assert_eq!(rows, ["3,#00000000,-1", "1,#FFFFFFFF,5", "1,#000000FF,0"]);
+}
+
+#[test]
+fn sample_input_columns() {
+    // Columns in the order kind,index,y,x,user,time
+    let log = "\
+user place\t5\t0\t0\tbob\t2022-01-01 00:00:00,000
+user place\t29\t0\t1\tbob\t2022-01-01 00:00:01,000
+";
+    let src = write_log("sample_input_columns.log", log);
+    let output = run(&[
+        "--input-columns",
+        "kind,index,y,x,user,time",
+        "stats",
+        "-s",
+        src.to_str().unwrap(),
+        "-m",
+        "color",
+        "--format",
+        "csv",
+        "--sample",
+        "2",
+    ]);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let out = String::from_utf8(output.stdout).unwrap();
+    let rows: Vec<&str> = out.lines().skip(1).collect();
+    assert_eq!(rows, ["1,#F02523FF,29", "1,#FFFFFFFF,5"]);
+}
+
+#[test]
+fn on_reset_split() {
+    // The second canvas starts a year earlier
+    let log = "\
+2022-01-01 00:00:00,000\tbob\t0\t0\t5\tuser place
+2022-01-01 00:00:01,000\tbob\t1\t0\t5\tuser place
+2021-01-01 00:00:00,000\tbob\t0\t0\t29\tuser place
+";
+    let src = write_log("on_reset_split.log", log);
+    let args = ["stats", "-s", src.to_str().unwrap(), "-m", "color", "--format", "csv"];
+    let output = run(&[&["--on-reset", "split"][..], &args].concat());
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    #[rustfmt::skip]
+    let expected = [
+        "Canvas 0:", "Amount,Color,Index", "2,#FFFFFFFF,5", "",
+        "Canvas 1:", "Amount,Color,Index", "1,#F02523FF,29",
+    ];
+    let out = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(out.lines().collect::<Vec<&str>>(), expected);
+}